@@ -0,0 +1,599 @@
+//! Client en ligne de commande de QR Craft
+//!
+//! Génère des QR codes sans démarrer le serveur, avec le même rendu que
+//! l'application, exporte les QR codes d'un répertoire de données et les
+//! imprime sur des planches d'étiquettes PDF.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use qr_craft::config::Settings;
+use qr_craft::models::labels::{LabelCaption, LabelSheet, PageSize};
+use qr_craft::models::payload::{
+    ContactFormat, ContactPayload, QrContent, WifiPayload, WifiSecurity,
+};
+use qr_craft::models::qr_code::{
+    ErrorCorrection, ExportFormat, FinderStyle, MarginEnabled, ModuleStyle, QrColor, QrId,
+    RenderOptions,
+};
+use qr_craft::services::export::{export_saved, render_export, select_saved};
+use qr_craft::services::labels::render_labels;
+use qr_craft::services::payload::{content_summary, encode_content};
+use qr_craft::services::storage::{open_existing_storage, QrStorage, StorageKind};
+
+#[derive(Parser)]
+#[command(
+    name = "qr-craft-cli",
+    version,
+    about = "Génère des QR codes en ligne de commande"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encode un texte ou une URL
+    Text {
+        /// Texte à encoder
+        text: String,
+        #[command(flatten)]
+        render: RenderArgs,
+    },
+    /// Encode les identifiants d'un réseau Wi-Fi
+    Wifi {
+        #[arg(long)]
+        ssid: String,
+        #[arg(long, default_value = "")]
+        password: String,
+        /// WPA, WEP ou nopass
+        #[arg(long, default_value = "WPA", value_parser = parse_security)]
+        security: WifiSecurity,
+        /// Réseau masqué
+        #[arg(long)]
+        hidden: bool,
+        #[command(flatten)]
+        render: RenderArgs,
+    },
+    /// Encode une fiche contact
+    Contact {
+        #[command(flatten)]
+        contact: ContactArgs,
+        #[command(flatten)]
+        render: RenderArgs,
+    },
+    /// Liste les QR codes sauvegardés
+    List {
+        #[command(flatten)]
+        data: DataArgs,
+    },
+    /// Exporte des QR codes sauvegardés (tous si aucun identifiant n'est donné)
+    Export {
+        ids: Vec<String>,
+        /// Répertoire de destination
+        #[arg(short = 'o', long)]
+        out_dir: PathBuf,
+        #[arg(short, long, default_value = "png", value_parser = parse_format)]
+        format: ExportFormat,
+        #[command(flatten)]
+        data: DataArgs,
+    },
+    /// Imprime des QR codes sauvegardés sur une planche d'étiquettes PDF
+    /// (tous si aucun identifiant n'est donné)
+    Labels {
+        ids: Vec<String>,
+        #[command(flatten)]
+        sheet: SheetArgs,
+        /// Fichier PDF ; la sortie standard si absent ou `-`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        data: DataArgs,
+    },
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    /// Côté de l'image, en pixels
+    #[arg(short, long, default_value_t = 256)]
+    size: u32,
+    /// Supprime la marge autour du QR code
+    #[arg(long)]
+    no_margin: bool,
+    /// Fond transparent
+    #[arg(long)]
+    transparent: bool,
+    /// Couleur des modules (#RGB, #RRGGBB ou #RRGGBBAA)
+    #[arg(long, default_value = "#000000", value_parser = parse_color)]
+    fg: QrColor,
+    /// Couleur du fond
+    #[arg(long, default_value = "#ffffff", value_parser = parse_color)]
+    bg: QrColor,
+    /// Niveau de correction d'erreur (L, M, Q ou H)
+    #[arg(long, default_value = "M", value_parser = parse_error_correction)]
+    ec: ErrorCorrection,
+    /// Forme des modules (square, circle, rounded, vertical-bars, horizontal-bars)
+    #[arg(long, default_value = "square", value_parser = parse_module_style)]
+    modules: ModuleStyle,
+    /// Forme des repères (square, rounded, circle)
+    #[arg(long, default_value = "square", value_parser = parse_finder_style)]
+    finders: FinderStyle,
+    /// Format de sortie (png ou svg) ; déduit de l'extension du fichier par défaut
+    #[arg(short, long, value_parser = parse_format)]
+    format: Option<ExportFormat>,
+    /// Fichier de sortie ; la sortie standard si absent ou `-`
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl RenderArgs {
+    fn options(&self) -> RenderOptions {
+        RenderOptions {
+            size: self.size,
+            transparent: self.transparent,
+            margin: MarginEnabled(!self.no_margin),
+            error_correction: self.ec,
+            foreground: self.fg,
+            background: self.bg,
+            logo: None,
+            module_style: self.modules,
+            finder_style: self.finders,
+        }
+    }
+
+    fn output(&self) -> Option<&Path> {
+        output_path(&self.output)
+    }
+
+    fn format(&self) -> ExportFormat {
+        self.format
+            .or_else(|| {
+                let extension = self.output()?.extension()?.to_str()?;
+                ExportFormat::from_extension(extension)
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Args)]
+struct ContactArgs {
+    #[arg(long, default_value = "")]
+    first_name: String,
+    #[arg(long, default_value = "")]
+    last_name: String,
+    #[arg(long, default_value = "")]
+    organisation: String,
+    /// Fonction
+    #[arg(long, default_value = "")]
+    title: String,
+    /// Téléphone (répétable)
+    #[arg(long = "phone")]
+    phones: Vec<String>,
+    /// Adresse e-mail (répétable)
+    #[arg(long = "email")]
+    emails: Vec<String>,
+    #[arg(long, default_value = "")]
+    url: String,
+    #[arg(long, default_value = "")]
+    address: String,
+    #[arg(long, default_value = "")]
+    note: String,
+    /// vcard ou mecard
+    #[arg(long, default_value = "vcard", value_parser = parse_contact_format)]
+    card: ContactFormat,
+}
+
+impl From<ContactArgs> for ContactPayload {
+    fn from(args: ContactArgs) -> Self {
+        ContactPayload {
+            first_name: args.first_name,
+            last_name: args.last_name,
+            organisation: args.organisation,
+            title: args.title,
+            phones: args.phones,
+            emails: args.emails,
+            url: args.url,
+            address: args.address,
+            note: args.note,
+            format: args.card,
+        }
+    }
+}
+
+#[derive(Args)]
+struct SheetArgs {
+    /// a4 ou letter
+    #[arg(long, default_value = "a4", value_parser = parse_page)]
+    page: PageSize,
+    #[arg(long, default_value_t = 3)]
+    columns: u32,
+    #[arg(long, default_value_t = 7)]
+    rows: u32,
+    /// Marge autour de la grille, en millimètres
+    #[arg(long, default_value_t = 10.0)]
+    margin: f64,
+    /// Espace entre deux étiquettes, en millimètres
+    #[arg(long, default_value_t = 3.0)]
+    spacing: f64,
+    /// Légende sous chaque QR code (content, id ou none)
+    #[arg(long, default_value = "content", value_parser = parse_caption)]
+    caption: LabelCaption,
+    /// Nombre d'étiquettes par QR code
+    #[arg(long, default_value_t = 1)]
+    copies: u32,
+}
+
+impl From<SheetArgs> for LabelSheet {
+    fn from(args: SheetArgs) -> Self {
+        LabelSheet {
+            page: args.page,
+            columns: args.columns,
+            rows: args.rows,
+            margin_mm: args.margin,
+            spacing_mm: args.spacing,
+            caption: args.caption,
+            copies: args.copies,
+        }
+    }
+}
+
+/// Répertoire de données ; les valeurs absentes viennent de la configuration
+/// de l'application (`qr-craft.toml`, variables `QR_CRAFT_*`).
+#[derive(Args)]
+struct DataArgs {
+    #[arg(short, long)]
+    data_dir: Option<PathBuf>,
+    /// json ou sqlite
+    #[arg(long, value_parser = parse_storage)]
+    storage: Option<StorageKind>,
+}
+
+impl DataArgs {
+    fn open(&self) -> Result<Box<dyn QrStorage>, String> {
+        let mut settings = Settings::load()?;
+        if let Some(dir) = &self.data_dir {
+            settings.data_dir = dir.clone();
+        }
+        if let Some(storage) = self.storage {
+            settings.storage = storage;
+        }
+        // Lecture seule : rien n'est créé ni réécrit dans les données
+        open_existing_storage(&settings)
+    }
+}
+
+fn parse_color(value: &str) -> Result<QrColor, String> {
+    QrColor::try_from(value.to_string())
+}
+
+fn parse_format(value: &str) -> Result<ExportFormat, String> {
+    ExportFormat::from_extension(value).ok_or_else(|| format!("Format inconnu : {}", value))
+}
+
+fn parse_error_correction(value: &str) -> Result<ErrorCorrection, String> {
+    ErrorCorrection::from_code(&value.to_ascii_uppercase())
+        .ok_or_else(|| format!("Niveau de correction inconnu : {}", value))
+}
+
+fn parse_module_style(value: &str) -> Result<ModuleStyle, String> {
+    ModuleStyle::from_code(value).ok_or_else(|| format!("Forme de module inconnue : {}", value))
+}
+
+fn parse_finder_style(value: &str) -> Result<FinderStyle, String> {
+    FinderStyle::from_code(value).ok_or_else(|| format!("Forme de repère inconnue : {}", value))
+}
+
+fn parse_security(value: &str) -> Result<WifiSecurity, String> {
+    WifiSecurity::ALL
+        .into_iter()
+        .find(|security| security.code().eq_ignore_ascii_case(value))
+        .ok_or_else(|| format!("Sécurité Wi-Fi inconnue : {}", value))
+}
+
+fn parse_contact_format(value: &str) -> Result<ContactFormat, String> {
+    ContactFormat::from_code(&value.to_ascii_lowercase())
+        .ok_or_else(|| format!("Format de fiche inconnu : {}", value))
+}
+
+fn parse_page(value: &str) -> Result<PageSize, String> {
+    PageSize::from_code(&value.to_ascii_lowercase())
+        .ok_or_else(|| format!("Format de page inconnu : {}", value))
+}
+
+fn parse_caption(value: &str) -> Result<LabelCaption, String> {
+    LabelCaption::from_code(value).ok_or_else(|| format!("Légende inconnue : {}", value))
+}
+
+fn parse_storage(value: &str) -> Result<StorageKind, String> {
+    StorageKind::from_code(value).ok_or_else(|| format!("Backend de stockage inconnu : {}", value))
+}
+
+/// Fichier de sortie, `-` désignant la sortie standard
+fn output_path(output: &Option<PathBuf>) -> Option<&Path> {
+    output.as_deref().filter(|path| *path != Path::new("-"))
+}
+
+/// Écrit le résultat dans le fichier ou sur la sortie standard.
+fn write_output(output: Option<&Path>, bytes: &[u8]) -> Result<(), String> {
+    match output {
+        Some(path) => std::fs::write(path, bytes)
+            .map_err(|e| format!("Écriture de {:?} impossible : {}", path, e)),
+        None => std::io::stdout()
+            .lock()
+            .write_all(bytes)
+            .map_err(|e| format!("Écriture sur la sortie standard impossible : {}", e)),
+    }
+}
+
+/// Rend le QR code et l'écrit dans le fichier ou sur la sortie standard.
+fn render(text: &str, args: &RenderArgs) -> Result<(), String> {
+    let bytes = render_export(text, &args.options(), args.format())?;
+    write_output(args.output(), &bytes)
+}
+
+fn render_content(content: QrContent, args: &RenderArgs) -> Result<(), String> {
+    let text = encode_content(&content).unwrap_or_else(|| Ok(String::new()))?;
+    render(&text, args)
+}
+
+fn list(data: &DataArgs) -> Result<(), String> {
+    let saved = data.open()?.list()?;
+    for qr in &saved.codes {
+        println!(
+            "{}\t{}\t{}",
+            qr.id,
+            qr.created_at,
+            content_summary(&qr.content, &qr.text)
+        );
+    }
+    for record in &saved.unreadable {
+        eprintln!("Illisible : {} ({})", record.source, record.error);
+    }
+    Ok(())
+}
+
+fn export(
+    ids: &[String],
+    out_dir: &Path,
+    format: ExportFormat,
+    data: &DataArgs,
+) -> Result<(), String> {
+    let ids = ids
+        .iter()
+        .map(|id| QrId::parse(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let codes = select_saved(data.open()?.as_ref(), &ids)?;
+    for path in export_saved(&codes, out_dir, format)? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn labels(
+    ids: &[String],
+    sheet: &LabelSheet,
+    output: Option<&Path>,
+    data: &DataArgs,
+) -> Result<(), String> {
+    let ids = ids
+        .iter()
+        .map(|id| QrId::parse(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let codes = select_saved(data.open()?.as_ref(), &ids)?;
+    write_output(output, &render_labels(&codes, sheet)?)
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Text { text, render: args } => render(&text, &args),
+        Command::Wifi {
+            ssid,
+            password,
+            security,
+            hidden,
+            render: args,
+        } => render_content(
+            QrContent::Wifi(WifiPayload {
+                ssid,
+                security,
+                password,
+                hidden,
+            }),
+            &args,
+        ),
+        Command::Contact {
+            contact,
+            render: args,
+        } => render_content(QrContent::Contact(contact.into()), &args),
+        Command::List { data } => list(&data),
+        Command::Export {
+            ids,
+            out_dir,
+            format,
+            data,
+        } => export(&ids, &out_dir, format, &data),
+        Command::Labels {
+            ids,
+            sheet,
+            output,
+            data,
+        } => labels(&ids, &sheet.into(), output_path(&output), &data),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Erreur : {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+    use qr_craft::models::qr_code::SavedQr;
+    use qr_craft::services::storage::JsonDirStorage;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("qr-craft-cli").chain(args.iter().copied()))
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("qr-craft-cli-test-{}", fastrand::u64(..)))
+    }
+
+    fn data_args(dir: &Path) -> DataArgs {
+        DataArgs {
+            data_dir: Some(dir.to_path_buf()),
+            storage: Some(StorageKind::Json),
+        }
+    }
+
+    /// Répertoire de données JSON contenant un QR code.
+    fn data_dir_with_code() -> PathBuf {
+        let dir = temp_dir();
+        let qr: SavedQr = serde_json::from_value(serde_json::json!({
+            "id": "qr-1",
+            "text": "https://example.com",
+            "size": 128,
+            "transparent": false,
+            "margin": true,
+            "created_at": "0",
+        }))
+        .unwrap();
+        JsonDirStorage::new(&dir).save(&qr).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let cli = parse(&[
+            "text", "bonjour", "--size", "128", "--fg", "#f00", "--ec", "h", "-o", "out.svg",
+        ])
+        .unwrap();
+        let Command::Text { text, render } = cli.command else {
+            panic!("sous-commande inattendue");
+        };
+        assert_eq!(text, "bonjour");
+        let options = render.options();
+        assert_eq!(options.size, 128);
+        assert_eq!(options.foreground, QrColor([255, 0, 0, 255]));
+        assert_eq!(options.error_correction, ErrorCorrection::H);
+        assert!(options.margin.0);
+        assert_eq!(render.format(), ExportFormat::Svg);
+        assert_eq!(render.output(), Some(Path::new("out.svg")));
+    }
+
+    #[test]
+    fn test_parse_stdout_and_explicit_format() {
+        let cli = parse(&["text", "a", "-o", "-", "--format", "svg", "--no-margin"]).unwrap();
+        let Command::Text { render, .. } = cli.command else {
+            panic!("sous-commande inattendue");
+        };
+        assert_eq!(render.output(), None);
+        assert_eq!(render.format(), ExportFormat::Svg);
+        assert!(!render.options().margin.0);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_arguments() {
+        let kind = |args: &[&str]| parse(args).err().map(|e| e.kind());
+        assert_eq!(
+            kind(&["text", "a", "--ec", "X"]),
+            Some(ErrorKind::ValueValidation)
+        );
+        assert_eq!(
+            kind(&["text", "a", "--fg", "rouge"]),
+            Some(ErrorKind::ValueValidation)
+        );
+        assert_eq!(
+            kind(&["list", "--storage", "csv"]),
+            Some(ErrorKind::ValueValidation)
+        );
+        assert_eq!(
+            kind(&["export", "qr-1"]),
+            Some(ErrorKind::MissingRequiredArgument)
+        );
+        assert_eq!(kind(&["wifi"]), Some(ErrorKind::MissingRequiredArgument));
+        assert_eq!(kind(&["inconnue"]), Some(ErrorKind::InvalidSubcommand));
+    }
+
+    #[test]
+    fn test_parse_data_and_sheet_arguments() {
+        let cli = parse(&[
+            "labels",
+            "qr-1",
+            "qr-2",
+            "--page",
+            "letter",
+            "--columns",
+            "2",
+            "--caption",
+            "id",
+            "-d",
+            "/tmp/donnees",
+            "--storage",
+            "sqlite",
+        ])
+        .unwrap();
+        let Command::Labels {
+            ids, sheet, data, ..
+        } = cli.command
+        else {
+            panic!("sous-commande inattendue");
+        };
+        assert_eq!(ids, ["qr-1", "qr-2"]);
+        let sheet = LabelSheet::from(sheet);
+        assert_eq!(sheet.page, PageSize::Letter);
+        assert_eq!((sheet.columns, sheet.rows), (2, 7));
+        assert_eq!(sheet.caption, LabelCaption::Id);
+        assert_eq!(data.data_dir, Some(PathBuf::from("/tmp/donnees")));
+        assert_eq!(data.storage, Some(StorageKind::Sqlite));
+    }
+
+    #[test]
+    fn test_read_commands_need_existing_data() {
+        let dir = temp_dir();
+        let data = data_args(&dir);
+        assert!(list(&data).is_err());
+        assert!(export(&[], &dir.join("export"), ExportFormat::Svg, &data).is_err());
+        assert!(labels(&[], &LabelSheet::default(), Some(&dir.join("a.pdf")), &data).is_err());
+        assert!(!dir.exists());
+
+        let sqlite = DataArgs {
+            storage: Some(StorageKind::Sqlite),
+            ..data_args(&dir)
+        };
+        assert!(list(&sqlite).is_err());
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_list_export_and_labels() {
+        let dir = data_dir_with_code();
+        let data = data_args(&dir);
+        let before = std::fs::read_to_string(dir.join("qr-1.json")).unwrap();
+        list(&data).unwrap();
+
+        let out_dir = dir.join("export");
+        export(&["qr-1".into()], &out_dir, ExportFormat::Svg, &data).unwrap();
+        let svg = std::fs::read_to_string(out_dir.join("qr-1.svg")).unwrap();
+        assert!(svg.contains("<svg"));
+        assert!(export(&["qr-2".into()], &out_dir, ExportFormat::Svg, &data).is_err());
+        assert!(export(&["../x".into()], &out_dir, ExportFormat::Svg, &data).is_err());
+
+        let pdf = dir.join("planche.pdf");
+        labels(&[], &LabelSheet::default(), Some(&pdf), &data).unwrap();
+        assert!(std::fs::read(&pdf).unwrap().starts_with(b"%PDF"));
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("qr-1.json")).unwrap(),
+            before
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,30 +1,55 @@
 //! Composant principal de l'application
+use crate::components::batch::BatchView;
 use crate::components::header::Header;
 use crate::components::qr_generator::QrGenerator;
 use crate::components::saved_qr_list::SavedQrList;
-use crate::models::qr_code::{SavedQr, UIQr};
+use crate::components::scan_stats::ScanStatsView;
+use crate::server_fns::get_ui_settings;
 use crate::theme::{Theme, ThemeMode};
 use dioxus::prelude::*;
+use qr_craft::config::UiSettings;
+use qr_craft::models::qr_code::{SavedList, UIQr};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Screen {
-    List, // Écran de liste des QR codes
-    Edit, // Écran d'édition/création de QR code
+    List,  // Écran de liste des QR codes
+    Edit,  // Écran d'édition/création de QR code
+    Stats, // Statistiques de scan des QR codes dynamiques
+    Batch, // Génération par lot à partir d'un fichier CSV
 }
 
 #[component]
 pub fn App() -> Element {
     use_context_provider(|| Signal::new(ThemeMode::Auto));
+    let mut settings = use_context_provider(|| Signal::new(UiSettings::default()));
 
     let screen = use_signal(|| Screen::List); // Commencer par l'écran de liste
-    let ui = use_signal(|| UIQr {
-        id: format!("qr-{}", fastrand::u64(..)),
-        text: crate::config::constants::DEFAULT_QR_TEXT.into(),
-        size: 256,
-        transparent: false,
-        margin: crate::models::qr_code::MarginEnabled(true),
+    let mut ui = use_signal(|| {
+        UIQr::new(
+            qr_craft::config::constants::DEFAULT_QR_TEXT.into(),
+            &UiSettings::default(),
+        )
+    });
+    let saved = use_signal(SavedList::default);
+
+    // Récupérer la configuration du serveur au démarrage
+    use_effect(move || {
+        spawn(async move {
+            match get_ui_settings().await {
+                Ok(loaded) => {
+                    // Le QR code initial n'a pas encore été modifié : appliquer les défauts
+                    if *screen.peek() == Screen::List {
+                        ui.set(UIQr::new(
+                            qr_craft::config::constants::DEFAULT_QR_TEXT.into(),
+                            &loaded,
+                        ));
+                    }
+                    settings.set(loaded);
+                }
+                Err(e) => eprintln!("get settings error: {}", e),
+            }
+        });
     });
-    let saved = use_signal(Vec::<SavedQr>::new);
 
     rsx! {
         Theme {}
@@ -37,6 +62,12 @@ pub fn App() -> Element {
                 Screen::Edit => rsx! {
                     QrGenerator { ui, saved, screen }
                 },
+                Screen::Stats => rsx! {
+                    ScanStatsView {}
+                },
+                Screen::Batch => rsx! {
+                    BatchView {}
+                },
             }
         }
     }
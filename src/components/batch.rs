@@ -0,0 +1,177 @@
+use crate::server_fns::generate_batch;
+use dioxus::prelude::*;
+use qr_craft::models::batch::BatchReport;
+use qr_craft::services::batch::{MANIFEST_NAME, MAX_BATCH_ROWS};
+
+/// Nom de l'archive téléchargée
+const ARCHIVE_NAME: &str = "qr-codes.zip";
+
+/// Écran de génération par lot à partir d'un fichier CSV
+#[component]
+pub fn BatchView() -> Element {
+    let mut report = use_signal(|| None::<BatchReport>);
+    let mut error = use_signal(|| None::<String>);
+    let mut running = use_signal(|| false);
+
+    let h_import_csv = move |e: FormEvent| async move {
+        let Some(engine) = e.files() else {
+            return;
+        };
+        let Some(name) = engine.files().into_iter().next() else {
+            return;
+        };
+        let Some(csv) = engine.read_file_to_string(&name).await else {
+            error.set(Some("Impossible de lire le fichier (UTF-8 attendu)".into()));
+            return;
+        };
+        running.set(true);
+        report.set(None);
+        match generate_batch(csv).await {
+            Ok(generated) => {
+                error.set(None);
+                report.set(Some(generated));
+            }
+            Err(e) => error.set(Some(e.to_string())),
+        }
+        running.set(false);
+    };
+
+    rsx! {
+        div { class: "row margin",
+            h5 { class: "max", "Génération par lot" }
+            button { class: "border",
+                i { "upload_file" }
+                span { "Choisir un fichier CSV" }
+                input {
+                    r#type: "file",
+                    accept: ".csv,text/csv",
+                    disabled: running(),
+                    onchange: h_import_csv,
+                }
+            }
+        }
+        article { class: "margin",
+            p {
+                "Une ligne par QR code (au plus {MAX_BATCH_ROWS}), séparateur « , » ou « ; ». "
+                "La colonne "
+                code { "text" }
+                " contient le texte à encoder ; pour un contenu structuré, la colonne "
+                code { "type" }
+                " vaut "
+                code { "wifi" }
+                " ("
+                code { "ssid" }
+                ", "
+                code { "password" }
+                ", "
+                code { "security" }
+                ", "
+                code { "hidden" }
+                ") ou "
+                code { "contact" }
+                " ("
+                code { "first_name" }
+                ", "
+                code { "last_name" }
+                ", "
+                code { "organisation" }
+                ", "
+                code { "title" }
+                ", "
+                code { "phone" }
+                ", "
+                code { "email" }
+                ", "
+                code { "url" }
+                ", "
+                code { "address" }
+                ", "
+                code { "note" }
+                ", "
+                code { "card" }
+                ")."
+            }
+            p { class: "small-text",
+                "Colonnes facultatives : "
+                code { "filename" }
+                " (nom de l'image), "
+                code { "size" }
+                ", "
+                code { "margin" }
+                ", "
+                code { "transparent" }
+                ", "
+                code { "foreground" }
+                ", "
+                code { "background" }
+                ", "
+                code { "error_correction" }
+                ", "
+                code { "module_style" }
+                ", "
+                code { "finder_style" }
+                ". Plusieurs téléphones ou e-mails se séparent par « | »."
+            }
+        }
+        if running() {
+            div { class: "center-align padding",
+                progress { class: "circle" }
+            }
+        }
+        if let Some(message) = error() {
+            p { class: "center-align small-text error-text",
+                i { class: "small", "error" }
+                " {message}"
+            }
+        }
+        if let Some(report) = report() {
+            article { class: "margin",
+                div { class: "row",
+                    div { class: "max",
+                        h6 { class: "no-margin",
+                            "{report.rows.len() - report.failed()} / {report.rows.len()} QR code(s) générés"
+                        }
+                        p { class: "small-text", "L'archive contient aussi le compte rendu {MANIFEST_NAME}" }
+                    }
+                    a {
+                        class: "button",
+                        href: "{report.archive}",
+                        download: ARCHIVE_NAME,
+                        i { "download" }
+                        span { "Télécharger (ZIP)" }
+                    }
+                }
+                table { class: "small-space",
+                    thead {
+                        tr {
+                            th { "Ligne" }
+                            th { "Fichier" }
+                            th { class: "max", "Contenu" }
+                        }
+                    }
+                    tbody {
+                        for row in report.rows.iter().cloned() {
+                            tr { key: "{row.line}",
+                                td { "{row.line}" }
+                                td {
+                                    if row.error.is_some() {
+                                        i { class: "small error-text", "error" }
+                                    } else {
+                                        "{row.file}"
+                                    }
+                                }
+                                td {
+                                    if let Some(e) = &row.error {
+                                        span { class: "error-text", "{e}" }
+                                    } else {
+                                        span { class: "truncate-text", "{row.summary}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
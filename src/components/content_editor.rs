@@ -0,0 +1,351 @@
+use dioxus::prelude::*;
+use qr_craft::config::UiSettings;
+use qr_craft::models::payload::{
+    ContactFormat, ContactPayload, QrContent, WifiPayload, WifiSecurity,
+};
+use qr_craft::models::qr_code::{DynamicLink, UIQr};
+use qr_craft::services::dynamic_link::validate_target;
+use qr_craft::services::payload::{contact_payload, encode_content, wifi_payload};
+use qr_craft::services::qr_code::capacity_info;
+
+/// Applique une modification au réseau Wi-Fi et réencode le texte du QR code
+fn update_wifi(mut ui: Signal<UIQr>, edit: impl FnOnce(&mut WifiPayload)) {
+    let mut v = (*ui.read()).clone();
+    if let QrContent::Wifi(wifi) = &mut v.content {
+        edit(wifi);
+        v.text = wifi_payload(wifi).unwrap_or_default();
+    }
+    ui.set(v);
+}
+
+/// Applique une modification à la fiche contact et réencode le texte du QR code
+fn update_contact(mut ui: Signal<UIQr>, edit: impl FnOnce(&mut ContactPayload)) {
+    let mut v = (*ui.read()).clone();
+    if let QrContent::Contact(contact) = &mut v.content {
+        edit(contact);
+        v.text = contact_payload(contact).unwrap_or_default();
+    }
+    ui.set(v);
+}
+
+/// Base des liens courts : URL publique configurée, sinon origine de la page
+fn public_base(settings: &UiSettings) -> String {
+    if !settings.public_url.is_empty() {
+        return settings.public_url.clone();
+    }
+    web_sys::window()
+        .and_then(|window| window.location().origin().ok())
+        .unwrap_or_else(|| "http://localhost:8080".into())
+}
+
+/// Active ou désactive le mode dynamique d'un contenu texte
+fn set_dynamic(mut ui: Signal<UIQr>, base: String, enabled: bool) {
+    let mut v = (*ui.read()).clone();
+    match (enabled, v.dynamic.take()) {
+        (true, None) => {
+            let link = DynamicLink::generate(v.text.clone());
+            v.text = link.short_url(&base);
+            v.dynamic = Some(link);
+        }
+        (false, Some(link)) => v.text = link.target,
+        (_, link) => v.dynamic = link,
+    }
+    ui.set(v);
+}
+
+/// Découpe une saisie multiligne (une valeur par ligne)
+fn lines(value: &str) -> Vec<String> {
+    value.lines().map(|line| line.to_string()).collect()
+}
+
+#[component]
+pub fn ContentEditor(ui: Signal<UIQr>) -> Element {
+    let settings = use_context::<Signal<UiSettings>>();
+    let content = ui.read().content.clone();
+    let dynamic = ui.read().dynamic.clone();
+    let error = match (encode_content(&content), &dynamic) {
+        (Some(Err(message)), _) => Some(message),
+        (_, Some(link)) => validate_target(&link.target).err(),
+        _ => settings.read().check_payload(&ui.read().text).err(),
+    };
+    let capacity = {
+        let ui = ui.read();
+        if ui.text.is_empty() {
+            None
+        } else {
+            Some(capacity_info(
+                &ui.text,
+                ui.render_options().effective_error_correction(),
+            ))
+        }
+    };
+
+    rsx! {
+        div { class: "row",
+            div { class: "field label suffix border",
+                select {
+                    value: "{content.code()}",
+                    onchange: move |e| {
+                        let mut v = (*ui.read()).clone();
+                        if let Some(link) = v.dynamic.take() {
+                            v.text = link.target;
+                        }
+                        match e.value().as_str() {
+                            "wifi" => {
+                                v.content = QrContent::Wifi(WifiPayload::default());
+                                v.text = String::new();
+                            }
+                            "contact" => {
+                                v.content = QrContent::Contact(ContactPayload::default());
+                                v.text = String::new();
+                            }
+                            _ => v.content = QrContent::Text,
+                        }
+                        ui.set(v);
+                    },
+                    option { value: "text", "Texte ou URL" }
+                    option { value: "wifi", "Wi-Fi" }
+                    option { value: "contact", "Contact" }
+                }
+                label { class: "active", "Contenu" }
+                i { "arrow_drop_down" }
+            }
+            match content.clone() {
+                QrContent::Text => rsx! {
+                    div { class: "field label border max",
+                        input {
+                            r#type: "text",
+                            placeholder: " ",
+                            value: match &dynamic {
+                                Some(link) => link.target.clone(),
+                                None => ui.read().text.clone(),
+                            },
+                            oninput: move |e| {
+                                let new_text = e.value();
+                                let mut v = (*ui.read()).clone();
+                                match &mut v.dynamic {
+                                    Some(link) => link.target = new_text,
+                                    None => v.text = new_text,
+                                }
+                                ui.set(v);
+                            },
+                        }
+                        label { class: "active",
+                            if dynamic.is_some() {
+                                "URL de destination"
+                            } else {
+                                "Texte ou URL"
+                            }
+                        }
+                    }
+                    // La redirection des QR codes dynamiques nécessite le serveur
+                    if !cfg!(feature = "offline") {
+                        label { class: "checkbox",
+                            input {
+                                r#type: "checkbox",
+                                checked: "{dynamic.is_some()}",
+                                onchange: move |e| {
+                                    let enabled = e.value() == "on" || e.value() == "true";
+                                    set_dynamic(ui, public_base(&settings.read()), enabled);
+                                },
+                            }
+                            span { "QR code dynamique" }
+                        }
+                    }
+                },
+                QrContent::Wifi(wifi) => rsx! {
+                    div { class: "field label border max",
+                        input {
+                            r#type: "text",
+                            placeholder: " ",
+                            value: "{wifi.ssid}",
+                            oninput: move |e| update_wifi(ui, |w| w.ssid = e.value()),
+                        }
+                        label { class: "active", "Nom du réseau (SSID)" }
+                    }
+                },
+                QrContent::Contact(contact) => rsx! {
+                    div { class: "field label suffix border",
+                        select {
+                            value: "{contact.format.code()}",
+                            onchange: move |e| {
+                                if let Some(format) = ContactFormat::from_code(&e.value()) {
+                                    update_contact(ui, |c| c.format = format);
+                                }
+                            },
+                            for format in ContactFormat::ALL {
+                                option { value: "{format.code()}", "{format.label()}" }
+                            }
+                        }
+                        label { class: "active", "Format" }
+                        i { "arrow_drop_down" }
+                    }
+                },
+            }
+        }
+        if dynamic.is_some() {
+            p { class: "small-text",
+                i { class: "small", "link" }
+                " Lien encodé : "
+                code { "{ui.read().text}" }
+                " — la destination reste modifiable après impression"
+            }
+        }
+        if let QrContent::Wifi(wifi) = ui.read().content.clone() {
+            div { class: "row",
+                div { class: "field label suffix border",
+                    select {
+                        value: "{wifi.security.code()}",
+                        onchange: move |e| {
+                            if let Some(security) = WifiSecurity::from_code(&e.value()) {
+                                update_wifi(ui, |w| w.security = security);
+                            }
+                        },
+                        for security in WifiSecurity::ALL {
+                            option { value: "{security.code()}", "{security.label()}" }
+                        }
+                    }
+                    label { class: "active", "Sécurité" }
+                    i { "arrow_drop_down" }
+                }
+                if wifi.security != WifiSecurity::None {
+                    div { class: "field label border max",
+                        input {
+                            r#type: "text",
+                            placeholder: " ",
+                            value: "{wifi.password}",
+                            oninput: move |e| update_wifi(ui, |w| w.password = e.value()),
+                        }
+                        label { class: "active", "Mot de passe" }
+                    }
+                }
+                label { class: "checkbox",
+                    input {
+                        r#type: "checkbox",
+                        checked: "{wifi.hidden}",
+                        onchange: move |e| {
+                            let hidden = e.value() == "on" || e.value() == "true";
+                            update_wifi(ui, |w| w.hidden = hidden);
+                        },
+                    }
+                    span { "Réseau masqué" }
+                }
+            }
+        }
+        if let QrContent::Contact(contact) = ui.read().content.clone() {
+            div { class: "grid",
+                div { class: "s6 field label border",
+                    input {
+                        r#type: "text",
+                        placeholder: " ",
+                        value: "{contact.first_name}",
+                        oninput: move |e| update_contact(ui, |c| c.first_name = e.value()),
+                    }
+                    label { class: "active", "Prénom" }
+                }
+                div { class: "s6 field label border",
+                    input {
+                        r#type: "text",
+                        placeholder: " ",
+                        value: "{contact.last_name}",
+                        oninput: move |e| update_contact(ui, |c| c.last_name = e.value()),
+                    }
+                    label { class: "active", "Nom" }
+                }
+                div { class: "s6 field label border",
+                    input {
+                        r#type: "text",
+                        placeholder: " ",
+                        value: "{contact.organisation}",
+                        oninput: move |e| update_contact(ui, |c| c.organisation = e.value()),
+                    }
+                    label { class: "active", "Organisation" }
+                }
+                div { class: "s6 field label border",
+                    input {
+                        r#type: "text",
+                        placeholder: " ",
+                        value: "{contact.title}",
+                        oninput: move |e| update_contact(ui, |c| c.title = e.value()),
+                    }
+                    label { class: "active", "Fonction" }
+                }
+                div { class: "s6 field label border textarea",
+                    textarea {
+                        placeholder: " ",
+                        value: contact.phones.join("\n"),
+                        oninput: move |e| update_contact(ui, |c| c.phones = lines(&e.value())),
+                    }
+                    label { class: "active", "Téléphones (un par ligne)" }
+                }
+                div { class: "s6 field label border textarea",
+                    textarea {
+                        placeholder: " ",
+                        value: contact.emails.join("\n"),
+                        oninput: move |e| update_contact(ui, |c| c.emails = lines(&e.value())),
+                    }
+                    label { class: "active", "E-mails (un par ligne)" }
+                }
+                div { class: "s6 field label border",
+                    input {
+                        r#type: "url",
+                        placeholder: " ",
+                        value: "{contact.url}",
+                        oninput: move |e| update_contact(ui, |c| c.url = e.value()),
+                    }
+                    label { class: "active", "Site web" }
+                }
+                div { class: "s6 field label border",
+                    input {
+                        r#type: "text",
+                        placeholder: " ",
+                        value: "{contact.address}",
+                        oninput: move |e| update_contact(ui, |c| c.address = e.value()),
+                    }
+                    label { class: "active", "Adresse" }
+                }
+                div { class: "s12 field label border textarea",
+                    textarea {
+                        placeholder: " ",
+                        value: "{contact.note}",
+                        oninput: move |e| update_contact(ui, |c| c.note = e.value()),
+                    }
+                    label { class: "active", "Note" }
+                }
+            }
+        }
+        match capacity {
+            Some(Ok(info)) => rsx! {
+                div { class: "row",
+                    progress {
+                        class: "max",
+                        value: "{info.used_bytes}",
+                        max: "{info.max_bytes}",
+                    }
+                    span { class: if info.is_dense() { "small-text orange-text" } else { "small-text" },
+                        "Version {info.version} ({info.modules}×{info.modules}) — {info.used_bytes} / {info.max_bytes} octets"
+                    }
+                }
+                if info.is_dense() {
+                    p { class: "small-text orange-text",
+                        i { class: "small", "warning" }
+                        " Code dense : réduisez le contenu ou préférez le format MeCard pour une meilleure lecture"
+                    }
+                }
+            },
+            Some(Err(message)) => rsx! {
+                p { class: "small-text error-text",
+                    i { class: "small", "error" }
+                    " {message}"
+                }
+            },
+            None => rsx! {},
+        }
+        if let Some(message) = error {
+            p { class: "small-text error-text",
+                i { class: "small", "error" }
+                " {message}"
+            }
+        }
+    }
+}
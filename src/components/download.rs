@@ -0,0 +1,14 @@
+use web_sys::{wasm_bindgen::JsCast, window, HtmlElement};
+
+/// Déclenche le téléchargement d'une data URL sous le nom donné
+pub fn download_data_url(data_url: &str, filename: &str) {
+    if let Some(document) = window().and_then(|window| window.document()) {
+        if let Ok(anchor) = document.create_element("a") {
+            if let Ok(anchor) = anchor.dyn_into::<HtmlElement>() {
+                let _ = anchor.set_attribute("href", data_url);
+                let _ = anchor.set_attribute("download", filename);
+                anchor.click();
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use crate::config::constants;
 use crate::theme::ThemeButton;
 use dioxus::prelude::*;
+use qr_craft::config::constants;
 
 #[component]
 pub fn Header(screen: Signal<super::app::Screen>) -> Element {
@@ -8,7 +8,7 @@ pub fn Header(screen: Signal<super::app::Screen>) -> Element {
     rsx! {
         header {
             nav {
-                if *screen.read() == super::app::Screen::Edit {
+                if *screen.read() != super::app::Screen::List {
                     button {
                         class: "circle transparent",
                         onclick: move |_| {
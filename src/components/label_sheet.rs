@@ -0,0 +1,160 @@
+use crate::components::download::download_data_url;
+use crate::server_fns::export_labels;
+use dioxus::prelude::*;
+use qr_craft::models::labels::{LabelCaption, LabelSheet, PageSize};
+use qr_craft::models::qr_code::QrId;
+use qr_craft::services::labels::{MAX_COPIES, MAX_GRID};
+
+/// Nom du document téléchargé
+const PDF_NAME: &str = "etiquettes.pdf";
+
+/// Met à jour la mise en page si la valeur saisie est un nombre
+fn update_number<T: std::str::FromStr>(
+    mut sheet: Signal<LabelSheet>,
+    value: String,
+    apply: impl FnOnce(&mut LabelSheet, T),
+) {
+    if let Ok(value) = value.parse() {
+        let mut v = sheet.read().clone();
+        apply(&mut v, value);
+        sheet.set(v);
+    }
+}
+
+/// Mise en page et impression des QR codes sélectionnés dans la liste
+#[component]
+pub fn LabelSheetPanel(mut selection: Signal<Vec<QrId>>) -> Element {
+    let mut sheet = use_signal(LabelSheet::default);
+    let mut error = use_signal(|| None::<String>);
+    let mut running = use_signal(|| false);
+
+    let h_download = move |_: MouseEvent| async move {
+        let ids = selection.read().iter().map(|id| id.to_string()).collect();
+        let layout = sheet.read().clone();
+        running.set(true);
+        match export_labels(ids, layout).await {
+            Ok(data_url) => {
+                error.set(None);
+                download_data_url(&data_url, PDF_NAME);
+            }
+            Err(e) => error.set(Some(e.to_string())),
+        }
+        running.set(false);
+    };
+
+    let current = sheet.read().clone();
+    rsx! {
+        article { class: "margin",
+            div { class: "row",
+                h6 { class: "max", "Planche d'étiquettes : {selection.read().len()} QR code(s)" }
+                button {
+                    class: "transparent circle small",
+                    onclick: move |_| selection.set(vec![]),
+                    i { "close" }
+                }
+            }
+            div { class: "row wrap",
+                div { class: "field label suffix border",
+                    select {
+                        value: "{current.page.code()}",
+                        onchange: move |e| {
+                            if let Some(page) = PageSize::from_code(&e.value()) {
+                                let mut v = sheet.read().clone();
+                                v.page = page;
+                                sheet.set(v);
+                            }
+                        },
+                        for page in PageSize::ALL {
+                            option { value: "{page.code()}", "{page.label()}" }
+                        }
+                    }
+                    label { class: "active", "Format" }
+                    i { "arrow_drop_down" }
+                }
+                div { class: "field label border",
+                    input {
+                        r#type: "number",
+                        min: "1",
+                        max: "{MAX_GRID}",
+                        value: "{current.columns}",
+                        oninput: move |e| update_number(sheet, e.value(), |s, n| s.columns = n),
+                    }
+                    label { class: "active", "Colonnes" }
+                }
+                div { class: "field label border",
+                    input {
+                        r#type: "number",
+                        min: "1",
+                        max: "{MAX_GRID}",
+                        value: "{current.rows}",
+                        oninput: move |e| update_number(sheet, e.value(), |s, n| s.rows = n),
+                    }
+                    label { class: "active", "Lignes" }
+                }
+                div { class: "field label border",
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        step: "0.5",
+                        value: "{current.margin_mm}",
+                        oninput: move |e| update_number(sheet, e.value(), |s, n| s.margin_mm = n),
+                    }
+                    label { class: "active", "Marge (mm)" }
+                }
+                div { class: "field label border",
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        step: "0.5",
+                        value: "{current.spacing_mm}",
+                        oninput: move |e| update_number(sheet, e.value(), |s, n| s.spacing_mm = n),
+                    }
+                    label { class: "active", "Espacement (mm)" }
+                }
+                div { class: "field label suffix border",
+                    select {
+                        value: "{current.caption.code()}",
+                        onchange: move |e| {
+                            if let Some(caption) = LabelCaption::from_code(&e.value()) {
+                                let mut v = sheet.read().clone();
+                                v.caption = caption;
+                                sheet.set(v);
+                            }
+                        },
+                        for caption in LabelCaption::ALL {
+                            option { value: "{caption.code()}", "{caption.label()}" }
+                        }
+                    }
+                    label { class: "active", "Légende" }
+                    i { "arrow_drop_down" }
+                }
+                div { class: "field label border",
+                    input {
+                        r#type: "number",
+                        min: "1",
+                        max: "{MAX_COPIES}",
+                        value: "{current.copies}",
+                        oninput: move |e| update_number(sheet, e.value(), |s, n| s.copies = n),
+                    }
+                    label { class: "active", "Exemplaires" }
+                }
+            }
+            if let Some(message) = error() {
+                p { class: "small-text error-text",
+                    i { class: "small", "error" }
+                    " {message}"
+                }
+            }
+            nav { class: "right-align",
+                button { disabled: running(), onclick: h_download,
+                    if running() {
+                        progress { class: "circle small" }
+                    } else {
+                        i { "picture_as_pdf" }
+                    }
+                    span { "Télécharger le PDF" }
+                }
+            }
+        }
+    }
+}
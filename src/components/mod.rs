@@ -1,4 +1,9 @@
 pub mod app;
+pub mod batch;
+pub mod content_editor;
+pub mod download;
 pub mod header;
+pub mod label_sheet;
 pub mod qr_generator;
 pub mod saved_qr_list;
+pub mod scan_stats;
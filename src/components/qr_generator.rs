@@ -1,32 +1,68 @@
-use crate::models::qr_code::{MarginEnabled, SavedQr, UIQr};
-use crate::services::qr_code::{generate_qr_code, list_saved, save_qr};
+use crate::components::content_editor::ContentEditor;
+use crate::server_fns::{generate_qr_code, generate_qr_svg, list_saved, save_qr};
 use dioxus::logger::tracing;
 use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
 use js_sys::Date;
+use qr_craft::config::UiSettings;
+use qr_craft::models::qr_code::{
+    ErrorCorrection, ExportFormat, FinderStyle, MarginEnabled, ModuleStyle, QrLogo, RenderedQr,
+    SavedList, SavedQr, UIQr, SCHEMA_VERSION,
+};
+use qr_craft::services::qr_code::{
+    check_contrast, decode_logo, local_preview_supported, render_preview, ContrastCheck,
+};
 use web_sys::{wasm_bindgen::JsCast, window, HtmlElement};
 
+/// Délai sans modification avant de rendre l'aperçu, en millisecondes
+const PREVIEW_DEBOUNCE_MS: u32 = 250;
+
 #[component]
 pub fn QrGenerator(
     ui: Signal<UIQr>,
-    saved: Signal<Vec<SavedQr>>,
+    saved: Signal<SavedList>,
     screen: Signal<super::app::Screen>,
 ) -> Element {
     tracing::debug!("QrGenerator render: {:?}", ui.read());
+    let settings = use_context::<Signal<UiSettings>>();
 
     // Signal séparé pour l'image générée afin d'éviter les boucles infinies
     let mut qr_image = use_signal(String::new);
+    // Résultat de la relecture du rendu (None tant qu'aucun rendu n'est disponible)
+    let mut scannable = use_signal(|| None::<bool>);
+    // L'utilisateur accepte d'enregistrer un QR code qui ne se relit pas
+    let mut force_save = use_signal(|| false);
+    let mut save_error = use_signal(|| None::<String>);
+    // Format choisi pour le téléchargement
+    let mut export_format = use_signal(ExportFormat::default);
+    // Message d'erreur lors de l'import du logo
+    let mut logo_error = use_signal(|| None::<String>);
 
     // Fonction pour télécharger l'image QR
     let h_download_qr = {
         move || async move {
-            let image_data = qr_image.read().clone();
+            let format = export_format();
+            let image_data = match format {
+                ExportFormat::Png => qr_image.read().clone(),
+                ExportFormat::Svg => {
+                    let cur = ui();
+                    match generate_qr_svg(cur.text.clone(), cur.render_options()).await {
+                        Ok(data_url) => data_url,
+                        Err(e) => {
+                            eprintln!("generate svg error: {}", e);
+                            return;
+                        }
+                    }
+                }
+            };
             if !image_data.is_empty() {
                 if let Some(window) = window() {
                     if let Some(document) = window.document() {
                         if let Ok(anchor) = document.create_element("a") {
                             if let Ok(anchor) = anchor.dyn_into::<HtmlElement>() {
+                                let filename = format!("qr-code.{}", format.extension());
                                 let _ = anchor.set_attribute("href", &image_data);
-                                let _ = anchor.set_attribute("download", "qr-code.png");
+                                let _ = anchor.set_attribute("download", &filename);
                                 anchor.click();
                             }
                         }
@@ -44,56 +80,106 @@ pub fn QrGenerator(
 
             let cur = (*ui.read()).clone();
 
-            let image_data = qr_image.read().clone();
-            if image_data.is_empty() {
+            // Aucun rendu vérifié pour la saisie courante
+            if qr_image.read().is_empty() || scannable().is_none() {
                 return;
             }
-
-            let base64 = image_data
-                .split_once(',')
-                .map(|(_, b64)| b64)
-                .unwrap_or(&image_data)
-                .to_string();
+            if scannable() == Some(false) && !force_save() {
+                save_error.set(Some(
+                    "Ce QR code ne se relit pas : corrigez-le ou forcez l'enregistrement.".into(),
+                ));
+                return;
+            }
+            save_error.set(None);
 
             let saved_q = SavedQr {
+                schema_version: SCHEMA_VERSION,
                 id: cur.id.clone(),
                 text: cur.text.clone(),
                 size: cur.size,
                 transparent: cur.transparent,
                 margin: cur.margin,
+                error_correction: cur.error_correction,
+                foreground: cur.foreground,
+                background: cur.background,
+                logo: cur.logo.clone(),
+                module_style: cur.module_style,
+                finder_style: cur.finder_style,
+                content: cur.content.clone(),
+                revision: cur.revision,
+                dynamic: cur.dynamic.clone(),
                 created_at: format!("{}", (Date::now() / 1000.0) as u64),
-                image_data: base64,
             };
 
-            if save_qr(saved_q).await.is_ok() {
-                if let Ok(list) = list_saved().await {
-                    saved.set(list);
+            match save_qr(saved_q, force_save()).await {
+                Ok(revision) => {
+                    if let Ok(list) = list_saved().await {
+                        saved.set(list);
+                    }
+                    let mut v = (*ui.read()).clone();
+                    v.revision = revision;
+                    ui.set(v);
                 }
-                let v = (*ui.read()).clone();
-                ui.set(v);
+                // Conflit de révision ou refus du serveur
+                Err(e) => save_error.set(Some(e.to_string())),
             }
         }
     };
 
-    // Effet pour générer automatiquement le QR code quand les paramètres changent
+    let mut show_rendered = move |rendered: RenderedQr| {
+        qr_image.set(rendered.data_url);
+        scannable.set(Some(rendered.scannable));
+        if rendered.scannable {
+            force_save.set(false);
+        }
+    };
+
+    // Rendu de l'aperçu en attente, annulé par la modification suivante
+    let mut pending_preview = use_signal(|| None::<Task>);
+
+    // Effet pour générer automatiquement le QR code quand les paramètres changent :
+    // rendu local (WASM) sans aller-retour serveur, sauf logo ou grande taille.
+    // Le rendu et sa relecture n'ont lieu qu'une fois la saisie interrompue.
     use_effect(move || {
         let text = ui().text.clone();
-        let size = ui().size;
-        let transparent = ui().transparent;
-        let margin = ui().margin;
-
-        if !text.is_empty() {
-            spawn(async move {
-                match generate_qr_code(text, size, transparent, margin).await {
-                    Ok(data_url) => qr_image.set(data_url),
-                    Err(e) => eprintln!("generate error: {}", e),
-                }
-            });
-        } else {
-            qr_image.set(String::new());
+        let options = ui().render_options();
+        let limits = settings.read().clone();
+
+        let debounce = pending_preview.take().map(|task| task.cancel()).is_some();
+        // L'ancien rendu ne correspond plus à la saisie : rien à enregistrer
+        // tant que le nouveau n'est pas vérifié
+        qr_image.set(String::new());
+        scannable.set(None);
+        if text.is_empty() {
+            return;
         }
+        let task = spawn(async move {
+            if debounce {
+                TimeoutFuture::new(PREVIEW_DEBOUNCE_MS).await;
+            }
+            let rendered = if local_preview_supported(&options) {
+                limits
+                    .check_request(&text, options.size)
+                    .and_then(|_| render_preview(&text, &options))
+            } else {
+                generate_qr_code(text, options)
+                    .await
+                    .map_err(|e| e.to_string())
+            };
+            match rendered {
+                Ok(rendered) => show_rendered(rendered),
+                Err(e) => {
+                    eprintln!("generate error: {}", e);
+                    qr_image.set(String::new());
+                    scannable.set(None);
+                }
+            }
+        });
+        pending_preview.set(Some(task));
     });
 
+    let contrast = check_contrast(ui.read().foreground, ui.read().background);
+
     rsx! {
         div { class: "",
             article { class: "",
@@ -108,25 +194,29 @@ pub fn QrGenerator(
                                     src: "{qr_image.read()}",
                                 }
                             }
+                            match scannable() {
+                                Some(true) => rsx! {
+                                    div { class: "center-align",
+                                        span { class: "chip small green white-text",
+                                            i { "check_circle" }
+                                            span { "Lisible" }
+                                        }
+                                    }
+                                },
+                                Some(false) => rsx! {
+                                    div { class: "center-align",
+                                        span { class: "chip small error",
+                                            i { "cancel" }
+                                            span { "Illisible" }
+                                        }
+                                    }
+                                },
+                                None => rsx! {},
+                            }
                         }
                     }
                     div { class: "s8 padding",
-                        div { class: "row",
-                            div { class: "field label border max",
-                                input {
-                                    r#type: "text",
-                                    placeholder: " ",
-                                    value: "{ui.read().text}",
-                                    oninput: move |e| {
-                                        let new_text = e.value();
-                                        let mut v = (*ui.read()).clone();
-                                        v.text = new_text;
-                                        ui.set(v);
-                                    },
-                                }
-                                label { class: "active", "Texte ou URL" }
-                            }
-                        }
+                        ContentEditor { ui }
                         div { class: "row",
                             div { class: "field label suffix border",
                                 select {
@@ -138,13 +228,31 @@ pub fn QrGenerator(
                                             ui.set(v);
                                         }
                                     },
-                                    option { value: "128", "128px" }
-                                    option { value: "256", "256px" }
-                                    option { value: "512", "512px" }
+                                    for size in settings.read().allowed_sizes.clone() {
+                                        option { value: "{size}", "{size}px" }
+                                    }
                                 }
                                 label { class: "active", "Taille" }
                                 i { "arrow_drop_down" }
                             }
+                            div { class: "field label suffix border",
+                                select {
+                                    value: "{ui.read().render_options().effective_error_correction().code()}",
+                                    disabled: ui.read().logo.is_some(),
+                                    onchange: move |e| {
+                                        if let Some(ec) = ErrorCorrection::from_code(&e.value()) {
+                                            let mut v = (*ui.read()).clone();
+                                            v.error_correction = ec;
+                                            ui.set(v);
+                                        }
+                                    },
+                                    for ec in ErrorCorrection::ALL {
+                                        option { value: "{ec.code()}", "{ec.label()}" }
+                                    }
+                                }
+                                label { class: "active", "Correction d'erreur" }
+                                i { "arrow_drop_down" }
+                            }
                             label { class: "checkbox",
                                 input {
                                     r#type: "checkbox",
@@ -171,14 +279,221 @@ pub fn QrGenerator(
                             }
                         }
                         div { class: "row",
+                            div { class: "field label suffix border",
+                                select {
+                                    value: "{ui.read().module_style.code()}",
+                                    onchange: move |e| {
+                                        if let Some(style) = ModuleStyle::from_code(&e.value()) {
+                                            let mut v = (*ui.read()).clone();
+                                            v.module_style = style;
+                                            ui.set(v);
+                                        }
+                                    },
+                                    for style in ModuleStyle::ALL {
+                                        option { value: "{style.code()}", "{style.label()}" }
+                                    }
+                                }
+                                label { class: "active", "Forme des modules" }
+                                i { "arrow_drop_down" }
+                            }
+                            div { class: "field label suffix border",
+                                select {
+                                    value: "{ui.read().finder_style.code()}",
+                                    onchange: move |e| {
+                                        if let Some(style) = FinderStyle::from_code(&e.value()) {
+                                            let mut v = (*ui.read()).clone();
+                                            v.finder_style = style;
+                                            ui.set(v);
+                                        }
+                                    },
+                                    for style in FinderStyle::ALL {
+                                        option { value: "{style.code()}", "{style.label()}" }
+                                    }
+                                }
+                                label { class: "active", "Forme des yeux" }
+                                i { "arrow_drop_down" }
+                            }
+                        }
+                        div { class: "row",
+                            div { class: "field label border",
+                                input {
+                                    r#type: "color",
+                                    value: "{ui.read().foreground.to_rgb_hex()}",
+                                    oninput: move |e| {
+                                        let mut v = (*ui.read()).clone();
+                                        if let Some(color) = v.foreground.with_rgb_hex(&e.value()) {
+                                            v.foreground = color;
+                                            ui.set(v);
+                                        }
+                                    },
+                                }
+                                label { class: "active", "Premier plan" }
+                            }
+                            div { class: "field label border",
+                                input {
+                                    r#type: "color",
+                                    value: "{ui.read().background.to_rgb_hex()}",
+                                    oninput: move |e| {
+                                        let mut v = (*ui.read()).clone();
+                                        if let Some(color) = v.background.with_rgb_hex(&e.value()) {
+                                            v.background = color;
+                                            ui.set(v);
+                                        }
+                                    },
+                                }
+                                label { class: "active", "Arrière-plan" }
+                            }
+                            match contrast {
+                                ContrastCheck::Good => rsx! {},
+                                ContrastCheck::Warning(message) => rsx! {
+                                    p { class: "small-text orange-text",
+                                        i { class: "small", "warning" }
+                                        " {message}"
+                                    }
+                                },
+                                ContrastCheck::Refused(message) => rsx! {
+                                    p { class: "small-text error-text",
+                                        i { class: "small", "error" }
+                                        " {message}"
+                                    }
+                                },
+                            }
+                        }
+                        div { class: "row",
+                            button { class: "border",
+                                i { "image" }
+                                span { "Logo" }
+                                input {
+                                    r#type: "file",
+                                    accept: "image/png,image/jpeg",
+                                    onchange: move |e| async move {
+                                        let Some(engine) = e.files() else {
+                                            return;
+                                        };
+                                        let Some(name) = engine.files().into_iter().next() else {
+                                            return;
+                                        };
+                                        let Some(bytes) = engine.read_file(&name).await else {
+                                            logo_error.set(Some("Impossible de lire le fichier".into()));
+                                            return;
+                                        };
+                                        let logo = QrLogo::new(base64::encode(&bytes));
+                                        match decode_logo(&logo) {
+                                            Ok(_) => {
+                                                logo_error.set(None);
+                                                let mut v = (*ui.read()).clone();
+                                                v.logo = Some(logo);
+                                                v.error_correction = ErrorCorrection::H;
+                                                ui.set(v);
+                                            }
+                                            Err(err) => logo_error.set(Some(err)),
+                                        }
+                                    },
+                                }
+                            }
+                            if let Some(logo) = ui.read().logo.clone() {
+                                div { class: "field label border",
+                                    input {
+                                        r#type: "range",
+                                        min: "5",
+                                        max: "{QrLogo::MAX_SCALE_PERCENT}",
+                                        value: "{logo.scale_percent}",
+                                        oninput: move |e| {
+                                            if let Ok(scale) = e.value().parse::<u32>() {
+                                                let mut v = (*ui.read()).clone();
+                                                if let Some(logo) = v.logo.as_mut() {
+                                                    logo.scale_percent = scale;
+                                                }
+                                                ui.set(v);
+                                            }
+                                        },
+                                    }
+                                    label { class: "active", "Taille du logo ({logo.scale_percent}%)" }
+                                }
+                                div { class: "field label border",
+                                    input {
+                                        r#type: "range",
+                                        min: "0",
+                                        max: "{QrLogo::MAX_PADDING_PERCENT}",
+                                        value: "{logo.padding_percent}",
+                                        oninput: move |e| {
+                                            if let Ok(padding) = e.value().parse::<u32>() {
+                                                let mut v = (*ui.read()).clone();
+                                                if let Some(logo) = v.logo.as_mut() {
+                                                    logo.padding_percent = padding;
+                                                }
+                                                ui.set(v);
+                                            }
+                                        },
+                                    }
+                                    label { class: "active", "Cadre ({logo.padding_percent}%)" }
+                                }
+                                button {
+                                    class: "circle transparent",
+                                    onclick: move |_| {
+                                        let mut v = (*ui.read()).clone();
+                                        v.logo = None;
+                                        ui.set(v);
+                                    },
+                                    i { "delete" }
+                                    div { class: "tooltip", "Retirer le logo" }
+                                }
+                            }
+                            if let Some(message) = logo_error() {
+                                p { class: "small-text error-text",
+                                    i { class: "small", "error" }
+                                    " {message}"
+                                }
+                            }
+                        }
+                        div { class: "row",
+                            div { class: "field label suffix border",
+                                select {
+                                    value: "{export_format().extension()}",
+                                    onchange: move |e| {
+                                        let format = match e.value().as_str() {
+                                            "svg" => ExportFormat::Svg,
+                                            _ => ExportFormat::Png,
+                                        };
+                                        export_format.set(format);
+                                    },
+                                    option { value: "png", "PNG" }
+                                    option { value: "svg", "SVG" }
+                                }
+                                label { class: "active", "Format" }
+                                i { "arrow_drop_down" }
+                            }
                             button { onclick: move |_| { h_download_qr() }, "Télécharger le QR Code" }
                             button {
                                 class: "circle secondary",
+                                disabled: scannable().is_none(),
                                 onclick: move |_| { h_save_qr() },
                                 i { "bookmark" }
                                 div { class: "tooltip", "Mettre en favoris le QR Code" }
                             }
                         }
+                        if scannable() == Some(false) {
+                            div { class: "row",
+                                p { class: "small-text error-text",
+                                    i { class: "small", "warning" }
+                                    " Le rendu ne se relit pas : vérifiez les couleurs, le logo, la forme des modules et la marge."
+                                }
+                                label { class: "checkbox",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: "{force_save()}",
+                                        onchange: move |e| force_save.set(e.value() == "on" || e.value() == "true"),
+                                    }
+                                    span { "Enregistrer malgré tout" }
+                                }
+                            }
+                        }
+                        if let Some(message) = save_error() {
+                            p { class: "small-text error-text",
+                                i { class: "small", "error" }
+                                " {message}"
+                            }
+                        }
                     }
                 }
             }
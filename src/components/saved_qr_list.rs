@@ -1,13 +1,40 @@
-use crate::models::qr_code::{SavedQr, UIQr};
-use crate::services::qr_code::{delete_saved, list_saved};
+use crate::components::label_sheet::LabelSheetPanel;
+use crate::server_fns::{decode_qr_image, delete_saved, list_saved, saved_thumbnail};
 use dioxus::prelude::*;
+use qr_craft::config::UiSettings;
+use qr_craft::models::qr_code::{QrId, SavedList, SavedQr, UIQr};
+use qr_craft::services::payload::content_summary;
+
+/// Miniature d'un QR code sauvegardé, rendue à la demande par le serveur
+#[component]
+fn SavedThumbnail(qr: ReadOnlySignal<SavedQr>) -> Element {
+    let thumbnail = use_resource(move || async move {
+        let id = qr.read().id.to_string();
+        saved_thumbnail(id).await
+    });
+
+    let view = match &*thumbnail.read() {
+        Some(Ok(src)) => rsx! {
+            img { src: "{src}", width: "96", height: "96" }
+        },
+        Some(Err(_)) => rsx! {
+            i { class: "extra", "broken_image" }
+        },
+        None => rsx! {
+            progress { class: "circle small" }
+        },
+    };
+    view
+}
 
 #[component]
 pub fn SavedQrList(
     ui: Signal<UIQr>,
-    saved: Signal<Vec<SavedQr>>,
+    saved: Signal<SavedList>,
     screen: Signal<super::app::Screen>,
 ) -> Element {
+    let settings = use_context::<Signal<UiSettings>>();
+
     // Charger automatiquement la liste des QR codes sauvegardés au démarrage
     use_effect(move || {
         let mut saved = saved;
@@ -20,13 +47,10 @@ pub fn SavedQrList(
 
     let h_create_new_qr = {
         move || async move {
-            ui.set(UIQr {
-                id: format!("qr-{}", fastrand::u64(..)),
-                text: crate::config::constants::DEFAULT_QR_TEXT.into(),
-                size: 256,
-                transparent: false,
-                margin: crate::models::qr_code::MarginEnabled(true),
-            });
+            ui.set(UIQr::new(
+                qr_craft::config::constants::DEFAULT_QR_TEXT.into(),
+                &settings.read(),
+            ));
             screen.set(super::app::Screen::Edit);
         }
     };
@@ -39,15 +63,71 @@ pub fn SavedQrList(
                 size: qr.size,
                 transparent: qr.transparent,
                 margin: qr.margin,
+                error_correction: qr.error_correction,
+                foreground: qr.foreground,
+                background: qr.background,
+                logo: qr.logo.clone(),
+                module_style: qr.module_style,
+                finder_style: qr.finder_style,
+                content: qr.content.clone(),
+                revision: qr.revision,
+                dynamic: qr.dynamic.clone(),
             });
             screen.set(super::app::Screen::Edit);
         }
     };
 
+    // QR codes sélectionnés pour la planche d'étiquettes
+    let mut selection = use_signal(Vec::<QrId>::new);
+
+    let mut h_toggle_selected = move |id: QrId, selected: bool| {
+        let mut ids = selection.read().clone();
+        ids.retain(|other| *other != id);
+        if selected {
+            ids.push(id);
+        }
+        selection.set(ids);
+    };
+
+    // Contenus décodés lors d'un import contenant plusieurs QR codes
+    let mut import_results = use_signal(Vec::<String>::new);
+    let mut import_error = use_signal(|| None::<String>);
+
+    let mut h_open_imported = move |text: String| {
+        import_results.set(vec![]);
+        ui.set(UIQr::new(text, &settings.read()));
+        screen.set(super::app::Screen::Edit);
+    };
+
+    let h_import_image = move |e: FormEvent| async move {
+        let Some(engine) = e.files() else {
+            return;
+        };
+        let Some(name) = engine.files().into_iter().next() else {
+            return;
+        };
+        let Some(bytes) = engine.read_file(&name).await else {
+            import_error.set(Some("Impossible de lire le fichier".into()));
+            return;
+        };
+        match decode_qr_image(base64::encode(&bytes)).await {
+            Ok(mut contents) => {
+                import_error.set(None);
+                if contents.len() == 1 {
+                    h_open_imported(contents.remove(0));
+                } else {
+                    import_results.set(contents);
+                }
+            }
+            Err(e) => import_error.set(Some(e.to_string())),
+        }
+    };
+
     let h_delete_qr = {
         to_owned![saved];
         move |qr: SavedQr| async move {
-            match delete_saved(qr.id.clone()).await {
+            h_toggle_selected(qr.id.clone(), false);
+            match delete_saved(qr.id.to_string()).await {
                 Ok(_) => {
                     if let Ok(list) = list_saved().await {
                         saved.set(list);
@@ -68,30 +148,28 @@ pub fn SavedQrList(
 
     rsx! {
         div { class: "",
-            if saved.read().is_empty() {
+            if saved.read().codes.is_empty() {
                 div { class: "center-align padding",
                     p { class: "large", "Aucun QR code sauvegardé" }
                     p { class: "small", "Cliquez sur \"Créer nouveau QR code\" pour commencer" }
                 }
             } else {
                 div { class: "grid margin",
-                    for qr in saved.read().iter().cloned() {
+                    for qr in saved.read().codes.iter().cloned() {
                         {
                             let qr_for_load = qr.clone();
                             let qr_for_delete = qr.clone();
+                            let id_for_select = qr.id.clone();
+                            let selected = selection.read().contains(&qr.id);
                             rsx! {
                                 div { key: "{qr.id}", class: "s12 m6 l4",
                                     article {
                                         div { class: "row",
                                             div { class: "bg-checkered",
-                                                img {
-                                                    src: "data:image/png;base64,{qr.image_data}",
-                                                    width: "96",
-                                                    height: "96",
-                                                }
+                                                SavedThumbnail { qr: qr.clone() }
                                             }
                                             div { class: "max row vertical no-space",
-                                                p { class: "truncate-text responsive", "{qr.text}" }
+                                                p { class: "truncate-text responsive", "{content_summary(&qr.content, &qr.text)}" }
                                                 p { class: "small-text",
                                                     "{qr.size}x{qr.size}px"
                                                     br {}
@@ -101,8 +179,25 @@ pub fn SavedQrList(
                                                         ""
                                                     }
                                                 }
+                                                if let Some(link) = &qr.dynamic {
+                                                    p { class: "small-text truncate-text",
+                                                        i { class: "small", "link" }
+                                                        " {link.target}"
+                                                    }
+                                                }
                                             }
                                             nav { class: "left-align vertical",
+                                                label { class: "checkbox",
+                                                    input {
+                                                        r#type: "checkbox",
+                                                        checked: "{selected}",
+                                                        onchange: move |e| {
+                                                            let selected = e.value() == "on" || e.value() == "true";
+                                                            h_toggle_selected(id_for_select.clone(), selected);
+                                                        },
+                                                    }
+                                                    span {}
+                                                }
                                                 button { class: "transparent circle small",
                                                     i { "more_vert" }
                                                     menu { class: "left no-wrap",
@@ -127,11 +222,80 @@ pub fn SavedQrList(
                     }
                 }
             }
+            if !selection.read().is_empty() {
+                LabelSheetPanel { selection }
+            }
+            if !import_results.read().is_empty() {
+                article { class: "margin",
+                    h6 { "Plusieurs QR codes trouvés dans l'image" }
+                    for text in import_results.read().iter().cloned() {
+                        {
+                            let text_for_open = text.clone();
+                            rsx! {
+                                div { class: "row",
+                                    p { class: "max truncate-text", "{text}" }
+                                    button {
+                                        class: "small",
+                                        onclick: move |_| { h_open_imported(text_for_open.clone()) },
+                                        "Ouvrir"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if !saved.read().unreadable.is_empty() {
+                article { class: "margin border orange-border",
+                    h6 {
+                        i { class: "orange-text", "warning" }
+                        " {saved.read().unreadable.len()} enregistrement(s) illisible(s)"
+                    }
+                    p { class: "small-text",
+                        "Ces fichiers n'ont pas pu être migrés vers le format courant ; ils sont conservés tels quels."
+                    }
+                    for record in saved.read().unreadable.iter().cloned() {
+                        div { key: "{record.source}", class: "row",
+                            code { "{record.source}" }
+                            span { class: "max small-text truncate-text", "{record.error}" }
+                        }
+                    }
+                }
+            }
+            if let Some(message) = import_error() {
+                p { class: "center-align small-text error-text",
+                    i { class: "small", "error" }
+                    " {message}"
+                }
+            }
             div { class: "center-align margin",
                 button { onclick: move |_| { h_create_new_qr() },
                     i { "add" }
                     "Créer nouveau QR code"
                 }
+                if saved.read().codes.iter().any(|qr| qr.dynamic.is_some()) {
+                    button {
+                        class: "border",
+                        onclick: move |_| screen.set(super::app::Screen::Stats),
+                        i { "bar_chart" }
+                        span { "Statistiques" }
+                    }
+                }
+                button {
+                    class: "border",
+                    onclick: move |_| screen.set(super::app::Screen::Batch),
+                    i { "table_view" }
+                    span { "Lot CSV" }
+                }
+                button { class: "border",
+                    i { "qr_code_scanner" }
+                    span { "Importer une image" }
+                    input {
+                        r#type: "file",
+                        accept: "image/png,image/jpeg",
+                        onchange: h_import_image,
+                    }
+                }
             }
         }
     }
@@ -0,0 +1,147 @@
+use crate::components::download::download_data_url;
+use crate::server_fns::{export_scans, scan_stats};
+use dioxus::prelude::*;
+use qr_craft::models::analytics::ScanStats;
+use qr_craft::services::analytics::day;
+
+/// Nombre de jours affichés dans l'historique d'un QR code
+const HISTORY_DAYS: usize = 30;
+
+/// Télécharge un export CSV des scans (d'un QR code ou de tous)
+async fn download_csv(id: Option<String>) -> Result<(), String> {
+    let filename = match &id {
+        Some(id) => format!("scans-{}.csv", id),
+        None => "scans.csv".to_string(),
+    };
+    let data_url = export_scans(id).await.map_err(|e| e.to_string())?;
+    download_data_url(&data_url, &filename);
+    Ok(())
+}
+
+/// Statistiques d'un QR code dynamique
+#[component]
+fn ScanStatsCard(stats: ScanStats, mut export_error: Signal<Option<String>>) -> Element {
+    let max = stats.per_day.iter().map(|d| d.count).max().unwrap_or(1);
+    let recent: Vec<_> = stats
+        .per_day
+        .iter()
+        .rev()
+        .take(HISTORY_DAYS)
+        .cloned()
+        .collect();
+    let id = stats.id.to_string();
+
+    rsx! {
+        article { class: "margin",
+            div { class: "row",
+                div { class: "max",
+                    h6 { class: "no-margin", "/r/{stats.slug}" }
+                    p { class: "small-text truncate-text", "{stats.target}" }
+                }
+                div { class: "center-align",
+                    h5 { class: "no-margin primary-text", "{stats.total}" }
+                    span { class: "small-text", "scan(s)" }
+                }
+                button {
+                    class: "border small",
+                    onclick: move |_| {
+                        let id = id.clone();
+                        async move {
+                            if let Err(e) = download_csv(Some(id)).await {
+                                export_error.set(Some(e));
+                            }
+                        }
+                    },
+                    i { "download" }
+                    span { "CSV" }
+                }
+            }
+            if let Some(at) = stats.last_scan {
+                p { class: "small-text", "Dernier scan : {day(at)}" }
+            }
+            if !stats.per_agent.is_empty() {
+                nav { class: "wrap",
+                    for (agent, count) in stats.per_agent.iter().cloned() {
+                        span { class: "chip small", "{agent.label()} : {count}" }
+                    }
+                }
+            }
+            if recent.is_empty() {
+                p { class: "small-text", "Aucun scan pour l'instant" }
+            } else {
+                table { class: "small-space",
+                    thead {
+                        tr {
+                            th { "Jour" }
+                            th { class: "max", "" }
+                            th { class: "right-align", "Scans" }
+                        }
+                    }
+                    tbody {
+                        for daily in recent {
+                            tr { key: "{daily.day}",
+                                td { "{daily.day}" }
+                                td {
+                                    progress { value: "{daily.count}", max: "{max}" }
+                                }
+                                td { class: "right-align", "{daily.count}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Écran des statistiques de scan des QR codes dynamiques
+#[component]
+pub fn ScanStatsView() -> Element {
+    let stats = use_resource(scan_stats);
+    let mut export_error = use_signal(|| None::<String>);
+
+    rsx! {
+        div { class: "row margin",
+            h5 { class: "max", "Statistiques de scan" }
+            button {
+                onclick: move |_| async move {
+                    if let Err(e) = download_csv(None).await {
+                        export_error.set(Some(e));
+                    }
+                },
+                i { "download" }
+                span { "Exporter tout (CSV)" }
+            }
+        }
+        if let Some(message) = export_error() {
+            p { class: "center-align small-text error-text",
+                i { class: "small", "error" }
+                " {message}"
+            }
+        }
+        match &*stats.read() {
+            Some(Ok(list)) if list.is_empty() => rsx! {
+                div { class: "center-align padding",
+                    p { class: "large", "Aucun QR code dynamique" }
+                    p { class: "small", "Activez \"QR code dynamique\" dans l'éditeur pour suivre les scans" }
+                }
+            },
+            Some(Ok(list)) => rsx! {
+                for item in list.iter().cloned() {
+                    ScanStatsCard { key: "{item.id}", stats: item, export_error }
+                }
+            },
+            Some(Err(e)) => rsx! {
+                p { class: "center-align error-text",
+                    i { class: "small", "error" }
+                    " {e}"
+                }
+            },
+            None => rsx! {
+                div { class: "center-align padding",
+                    progress { class: "circle" }
+                }
+            },
+        }
+    }
+}
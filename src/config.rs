@@ -1,7 +1,349 @@
 //! Configuration de l'application
 
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::storage::StorageKind;
+
 /// Constantes de l'application
 pub mod constants {
     pub const APP_NAME: &str = "QR Craft";
     pub const DEFAULT_QR_TEXT: &str = "https://example.com";
 }
+
+/// Fichier de configuration lu s'il existe (surchargeable par `QR_CRAFT_CONFIG`)
+pub const DEFAULT_CONFIG_FILE: &str = "qr-craft.toml";
+
+/// Préfixe des variables d'environnement
+const ENV_PREFIX: &str = "QR_CRAFT_";
+
+/// Réglages partagés avec l'interface
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    /// Taille proposée pour un nouveau QR code, en pixels
+    pub default_size: u32,
+    /// Marge activée pour un nouveau QR code
+    pub default_margin: bool,
+    /// Longueur maximale du contenu encodé, en octets
+    pub max_payload_length: usize,
+    /// Tailles proposées et acceptées par le serveur, en pixels
+    pub allowed_sizes: Vec<u32>,
+    /// URL publique du serveur pour les liens courts des QR codes dynamiques
+    /// (vide : origine de la page)
+    pub public_url: String,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        UiSettings {
+            default_size: 256,
+            default_margin: true,
+            max_payload_length: 2048,
+            allowed_sizes: vec![128, 256, 512],
+            public_url: String::new(),
+        }
+    }
+}
+
+impl UiSettings {
+    pub fn check_payload(&self, text: &str) -> Result<(), String> {
+        if text.len() > self.max_payload_length {
+            return Err(format!(
+                "Le contenu dépasse la longueur maximale autorisée ({} / {} octets)",
+                text.len(),
+                self.max_payload_length
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn check_size(&self, size: u32) -> Result<(), String> {
+        if !self.allowed_sizes.contains(&size) {
+            return Err(format!("Taille non autorisée : {}px", size));
+        }
+        Ok(())
+    }
+
+    /// Vérifie le contenu et la taille d'une demande de rendu.
+    pub fn check_request(&self, text: &str, size: u32) -> Result<(), String> {
+        self.check_payload(text)?;
+        self.check_size(size)
+    }
+}
+
+/// Réglages du serveur
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Répertoire des QR codes sauvegardés
+    pub data_dir: PathBuf,
+    /// Backend de stockage
+    pub storage: StorageKind,
+    /// Réécrire au format courant les enregistrements migrés à la lecture
+    pub rewrite_migrated: bool,
+    /// Fichier CSV de plages IP → pays pour localiser les scans (optionnel)
+    pub geoip_file: Option<PathBuf>,
+    /// Adresses des reverse proxys dont l'en-tête `X-Forwarded-For` est lu
+    /// (vide : adresse de la connexion)
+    pub trusted_proxies: Vec<IpAddr>,
+    #[serde(flatten)]
+    pub ui: UiSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            data_dir: PathBuf::from("data"),
+            storage: StorageKind::default(),
+            rewrite_migrated: true,
+            geoip_file: None,
+            trusted_proxies: Vec::new(),
+            ui: UiSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Construit les réglages à partir d'un fichier TOML optionnel, puis des
+    /// variables d'environnement `QR_CRAFT_*` qui ont priorité.
+    pub fn from_sources(
+        toml: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        let mut settings = match toml {
+            Some(toml) => ::toml::from_str::<Settings>(toml)
+                .map_err(|e| format!("Fichier de configuration invalide : {}", e))?,
+            None => Settings::default(),
+        };
+
+        let var = |name: &str| env(&format!("{}{}", ENV_PREFIX, name));
+        if let Some(value) = var("DATA_DIR") {
+            settings.data_dir = PathBuf::from(value);
+        }
+        if let Some(value) = var("STORAGE") {
+            settings.storage = StorageKind::from_code(&value)
+                .ok_or_else(|| format!("Backend de stockage inconnu : {:?}", value))?;
+        }
+        if let Some(value) = var("REWRITE_MIGRATED") {
+            settings.rewrite_migrated = parse_env("REWRITE_MIGRATED", &value)?;
+        }
+        if let Some(value) = var("GEOIP_FILE") {
+            settings.geoip_file = (!value.is_empty()).then(|| PathBuf::from(value));
+        }
+        if let Some(value) = var("TRUSTED_PROXIES") {
+            settings.trusted_proxies = value
+                .split(',')
+                .filter(|ip| !ip.trim().is_empty())
+                .map(|ip| parse_env("TRUSTED_PROXIES", ip))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(value) = var("DEFAULT_SIZE") {
+            settings.ui.default_size = parse_env("DEFAULT_SIZE", &value)?;
+        }
+        if let Some(value) = var("DEFAULT_MARGIN") {
+            settings.ui.default_margin = parse_env("DEFAULT_MARGIN", &value)?;
+        }
+        if let Some(value) = var("MAX_PAYLOAD_LENGTH") {
+            settings.ui.max_payload_length = parse_env("MAX_PAYLOAD_LENGTH", &value)?;
+        }
+        if let Some(value) = var("PUBLIC_URL") {
+            settings.ui.public_url = value;
+        }
+        if let Some(value) = var("ALLOWED_SIZES") {
+            settings.ui.allowed_sizes = value
+                .split(',')
+                .map(|size| parse_env("ALLOWED_SIZES", size))
+                .collect::<Result<_, _>>()?;
+        }
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Lit le fichier de configuration et l'environnement du processus.
+    pub fn load() -> Result<Self, String> {
+        let toml = match std::env::var(format!("{}CONFIG", ENV_PREFIX)) {
+            Ok(path) => Some(read_config(Path::new(&path))?),
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Some(read_config(Path::new(DEFAULT_CONFIG_FILE))?)
+            }
+            Err(_) => None,
+        };
+        Self::from_sources(toml.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.ui.allowed_sizes.is_empty() {
+            return Err("La liste des tailles autorisées est vide".into());
+        }
+        if let Some(size) = self.ui.allowed_sizes.iter().find(|&&s| s == 0) {
+            return Err(format!("Taille autorisée invalide : {}", size));
+        }
+        if !self.ui.allowed_sizes.contains(&self.ui.default_size) {
+            return Err(format!(
+                "La taille par défaut {}px ne fait pas partie des tailles autorisées",
+                self.ui.default_size
+            ));
+        }
+        if self.ui.max_payload_length == 0 {
+            return Err("La longueur maximale du contenu doit être positive".into());
+        }
+        let public_url = &self.ui.public_url;
+        if !public_url.is_empty()
+            && !public_url.starts_with("http://")
+            && !public_url.starts_with("https://")
+        {
+            return Err(format!("URL publique invalide : {:?}", public_url));
+        }
+        Ok(())
+    }
+}
+
+fn read_config(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "Lecture du fichier de configuration {:?} impossible : {}",
+            path, e
+        )
+    })
+}
+
+fn parse_env<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Valeur invalide pour {}{} : {:?}", ENV_PREFIX, name, value))
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Installe les réglages à la place de ceux du fichier de configuration et de
+/// l'environnement ; échoue s'ils ont déjà été chargés.
+pub fn init_settings(settings: Settings) -> Result<(), String> {
+    settings.validate()?;
+    SETTINGS
+        .set(settings)
+        .map_err(|_| "Les réglages ont déjà été chargés".to_string())
+}
+
+/// Réglages du serveur, chargés au premier appel.
+pub fn settings() -> Result<&'static Settings, String> {
+    if let Some(settings) = SETTINGS.get() {
+        return Ok(settings);
+    }
+    let settings = Settings::load()?;
+    Ok(SETTINGS.get_or_init(|| settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_defaults_without_sources() {
+        let settings = Settings::from_sources(None, env(&[])).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_toml_file() {
+        let toml = r#"
+            data_dir = "/var/lib/qr-craft"
+            storage = "sqlite"
+            default_size = 512
+            default_margin = false
+            allowed_sizes = [256, 512, 1024]
+            rewrite_migrated = false
+            geoip_file = "/usr/share/geoip/country.csv"
+            trusted_proxies = ["127.0.0.1", "::1"]
+        "#;
+        let settings = Settings::from_sources(Some(toml), env(&[])).unwrap();
+        assert_eq!(settings.data_dir, PathBuf::from("/var/lib/qr-craft"));
+        assert_eq!(settings.storage, StorageKind::Sqlite);
+        assert!(!settings.rewrite_migrated);
+        assert_eq!(
+            settings.geoip_file,
+            Some(PathBuf::from("/usr/share/geoip/country.csv"))
+        );
+        assert_eq!(
+            settings.trusted_proxies,
+            vec![
+                "127.0.0.1".parse::<IpAddr>().unwrap(),
+                "::1".parse().unwrap()
+            ]
+        );
+        assert_eq!(settings.ui.default_size, 512);
+        assert!(!settings.ui.default_margin);
+        assert_eq!(settings.ui.max_payload_length, 2048);
+        assert_eq!(settings.ui.allowed_sizes, vec![256, 512, 1024]);
+    }
+
+    #[test]
+    fn test_env_overrides_toml() {
+        let toml = r#"data_dir = "/from/file""#;
+        let settings = Settings::from_sources(
+            Some(toml),
+            env(&[
+                ("QR_CRAFT_DATA_DIR", "/data"),
+                ("QR_CRAFT_ALLOWED_SIZES", "128, 1024"),
+                ("QR_CRAFT_DEFAULT_SIZE", "1024"),
+                ("QR_CRAFT_MAX_PAYLOAD_LENGTH", "300"),
+                ("QR_CRAFT_PUBLIC_URL", "https://qr.example.com"),
+                ("QR_CRAFT_GEOIP_FILE", ""),
+                ("QR_CRAFT_TRUSTED_PROXIES", "10.0.0.1, 10.0.0.2"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(settings.data_dir, PathBuf::from("/data"));
+        assert_eq!(settings.ui.allowed_sizes, vec![128, 1024]);
+        assert_eq!(settings.ui.default_size, 1024);
+        assert_eq!(settings.ui.max_payload_length, 300);
+        assert_eq!(settings.ui.public_url, "https://qr.example.com");
+        assert_eq!(settings.geoip_file, None);
+        assert_eq!(settings.trusted_proxies.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        assert!(Settings::from_sources(Some("default_size = \"big\""), env(&[])).is_err());
+        assert!(Settings::from_sources(None, env(&[("QR_CRAFT_DEFAULT_SIZE", "300")])).is_err());
+        assert!(Settings::from_sources(None, env(&[("QR_CRAFT_ALLOWED_SIZES", "")])).is_err());
+        assert!(Settings::from_sources(None, env(&[("QR_CRAFT_STORAGE", "redis")])).is_err());
+        assert!(
+            Settings::from_sources(None, env(&[("QR_CRAFT_PUBLIC_URL", "qr.example.com")]))
+                .is_err()
+        );
+        assert!(
+            Settings::from_sources(None, env(&[("QR_CRAFT_DEFAULT_MARGIN", "peut-être")])).is_err()
+        );
+        assert!(
+            Settings::from_sources(None, env(&[("QR_CRAFT_TRUSTED_PROXIES", "proxy.local")]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_ui_checks() {
+        let ui = UiSettings {
+            max_payload_length: 5,
+            ..UiSettings::default()
+        };
+        assert!(ui.check_payload("12345").is_ok());
+        assert!(ui.check_payload("123456").is_err());
+        assert!(ui.check_size(256).is_ok());
+        assert!(ui.check_size(300).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+//! Bibliothèque QR Craft : rendu, contenus structurés, modèles et stockage
+//!
+//! Compile sans Dioxus ni web-sys, pour être utilisée par d'autres services
+//! Rust ; l'application Dioxus et `qr-craft-cli` s'appuient dessus. La
+//! fonctionnalité `sqlite` ajoute le stockage SQLite, `server` les services
+//! HTTP (images PNG, API REST).
+
+#[cfg(all(feature = "offline", feature = "server"))]
+compile_error!(
+    "Les fonctionnalités `offline` et `server` sont incompatibles : \
+     `offline` exécute les fonctions serveur dans le navigateur"
+);
+
+pub mod config;
+pub mod models;
+pub mod services;
@@ -1,15 +1,34 @@
 //! Point d'entrée de l'application QR Craft
 
-use dioxus::launch;
-
 mod components;
-mod config;
-mod models;
-mod services;
+#[cfg(feature = "server")]
+mod server;
+mod server_fns;
 mod theme;
 
-use components::app::App;
+#[cfg(not(any(feature = "server", feature = "offline")))]
+fn main() {
+    dioxus::launch(components::app::App);
+}
+
+/// Mode hors ligne : aucun rendu serveur à hydrater, QR codes conservés dans
+/// le `localStorage` du navigateur
+#[cfg(feature = "offline")]
+fn main() {
+    use qr_craft::config::{init_settings, Settings};
+    use qr_craft::services::storage::StorageKind;
+
+    let settings = Settings {
+        storage: StorageKind::Browser,
+        ..Settings::default()
+    };
+    if let Err(e) = init_settings(settings) {
+        eprintln!("Réglages hors ligne : {}", e);
+    }
+    dioxus::web::launch::launch_cfg(components::app::App, dioxus::web::Config::new());
+}
 
+#[cfg(feature = "server")]
 fn main() {
-    launch(App);
+    server::serve();
 }
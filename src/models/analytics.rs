@@ -0,0 +1,84 @@
+//! Modèles des statistiques de scan des QR codes dynamiques
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::qr_code::QrId;
+
+/// Catégorie d'appareil déduite du User-Agent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentClass {
+    Mobile,
+    Desktop,
+    /// Robots, aperçus de liens et clients en ligne de commande
+    Bot,
+    #[default]
+    Other,
+}
+
+impl AgentClass {
+    pub const ALL: [AgentClass; 4] = [
+        AgentClass::Mobile,
+        AgentClass::Desktop,
+        AgentClass::Bot,
+        AgentClass::Other,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AgentClass::Mobile => "mobile",
+            AgentClass::Desktop => "desktop",
+            AgentClass::Bot => "bot",
+            AgentClass::Other => "other",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AgentClass::Mobile => "Mobile",
+            AgentClass::Desktop => "Ordinateur",
+            AgentClass::Bot => "Robot",
+            AgentClass::Other => "Autre",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|agent| agent.code() == code)
+    }
+}
+
+/// Passage par la redirection d'un QR code dynamique ; l'adresse IP n'est
+/// pas conservée, seulement le pays qui en est déduit.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScanEvent {
+    pub qr_id: QrId,
+    /// Horodatage, en secondes depuis l'époque Unix
+    pub at: u64,
+    pub agent: AgentClass,
+    /// Domaine de la page d'origine, si le navigateur l'a transmis
+    pub referrer: Option<String>,
+    /// Code pays ISO 3166-1 alpha-2
+    pub country: Option<String>,
+}
+
+/// Nombre de scans d'une journée (UTC)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailyScans {
+    /// Date au format `AAAA-MM-JJ`
+    pub day: String,
+    pub count: u64,
+}
+
+/// Statistiques d'un QR code dynamique
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScanStats {
+    pub id: QrId,
+    pub slug: String,
+    pub target: String,
+    pub total: u64,
+    /// Jours ayant au moins un scan, du plus ancien au plus récent
+    pub per_day: Vec<DailyScans>,
+    pub per_agent: Vec<(AgentClass, u64)>,
+    /// Horodatage du dernier scan
+    pub last_scan: Option<u64>,
+}
@@ -0,0 +1,31 @@
+//! Modèles de la génération par lot à partir d'un fichier CSV
+
+use serde::{Deserialize, Serialize};
+
+/// Résultat d'une ligne du fichier CSV
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchRow {
+    /// Numéro de ligne dans le fichier (l'en-tête est la ligne 1)
+    pub line: usize,
+    /// Nom de l'image dans l'archive
+    pub file: String,
+    /// Libellé court du contenu encodé
+    pub summary: String,
+    /// Motif de l'échec ; la ligne est absente de l'archive
+    pub error: Option<String>,
+}
+
+/// Lot généré : compte rendu par ligne et archive ZIP des images
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub rows: Vec<BatchRow>,
+    /// Archive ZIP (images PNG et manifeste) sous forme de data URL
+    pub archive: String,
+}
+
+impl BatchReport {
+    /// Nombre de lignes en erreur
+    pub fn failed(&self) -> usize {
+        self.rows.iter().filter(|row| row.error.is_some()).count()
+    }
+}
@@ -0,0 +1,110 @@
+//! Modèles des planches d'étiquettes imprimables (PDF)
+
+use serde::{Deserialize, Serialize};
+
+/// Format de page
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    pub const ALL: [PageSize; 2] = [PageSize::A4, PageSize::Letter];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            PageSize::A4 => "a4",
+            PageSize::Letter => "letter",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PageSize::A4 => "A4 (210 × 297 mm)",
+            PageSize::Letter => "Letter (8,5 × 11 po)",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|page| page.code() == code)
+    }
+
+    /// Largeur et hauteur en millimètres
+    pub fn dimensions_mm(&self) -> (f64, f64) {
+        match self {
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::Letter => (215.9, 279.4),
+        }
+    }
+}
+
+/// Légende imprimée sous chaque QR code
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelCaption {
+    /// Aucune légende
+    None,
+    /// Libellé court du contenu (texte, réseau Wi-Fi, nom du contact)
+    #[default]
+    Content,
+    /// Identifiant du QR code sauvegardé
+    Id,
+}
+
+impl LabelCaption {
+    pub const ALL: [LabelCaption; 3] =
+        [LabelCaption::None, LabelCaption::Content, LabelCaption::Id];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            LabelCaption::None => "none",
+            LabelCaption::Content => "content",
+            LabelCaption::Id => "id",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LabelCaption::None => "Aucune",
+            LabelCaption::Content => "Contenu",
+            LabelCaption::Id => "Identifiant",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|caption| caption.code() == code)
+    }
+}
+
+/// Mise en page d'une planche d'étiquettes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelSheet {
+    pub page: PageSize,
+    pub columns: u32,
+    pub rows: u32,
+    /// Marge autour de la grille, en millimètres
+    pub margin_mm: f64,
+    /// Espace entre deux étiquettes, en millimètres
+    pub spacing_mm: f64,
+    pub caption: LabelCaption,
+    /// Nombre d'étiquettes imprimées pour chaque QR code
+    pub copies: u32,
+}
+
+impl Default for LabelSheet {
+    fn default() -> Self {
+        LabelSheet {
+            page: PageSize::default(),
+            columns: 3,
+            rows: 7,
+            margin_mm: 10.0,
+            spacing_mm: 3.0,
+            caption: LabelCaption::default(),
+            copies: 1,
+        }
+    }
+}
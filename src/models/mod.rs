@@ -1,3 +1,7 @@
 //! Modèles de données pour l'application QR Craft
 
+pub mod analytics;
+pub mod batch;
+pub mod labels;
+pub mod payload;
 pub mod qr_code;
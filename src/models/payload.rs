@@ -0,0 +1,125 @@
+//! Modèles des contenus structurés encodés dans les QR codes
+
+use serde::{Deserialize, Serialize};
+
+/// Type de sécurité d'un réseau Wi-Fi
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WifiSecurity {
+    #[default]
+    Wpa,
+    Wep,
+    /// Réseau ouvert, sans mot de passe
+    None,
+}
+
+impl WifiSecurity {
+    pub const ALL: [WifiSecurity; 3] = [WifiSecurity::Wpa, WifiSecurity::Wep, WifiSecurity::None];
+
+    /// Valeur du champ `T:` de la charge utile `WIFI:`
+    pub fn code(&self) -> &'static str {
+        match self {
+            WifiSecurity::Wpa => "WPA",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::None => "nopass",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WifiSecurity::Wpa => "WPA/WPA2/WPA3",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::None => "Aucune",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|security| security.code() == code)
+    }
+}
+
+/// Réseau Wi-Fi
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WifiPayload {
+    pub ssid: String,
+    pub security: WifiSecurity,
+    pub password: String,
+    pub hidden: bool,
+}
+
+/// Format de sérialisation d'une fiche contact
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContactFormat {
+    #[default]
+    VCard,
+    /// Plus compact que vCard, mais sans fonction (titre)
+    MeCard,
+}
+
+impl ContactFormat {
+    pub const ALL: [ContactFormat; 2] = [ContactFormat::VCard, ContactFormat::MeCard];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContactFormat::VCard => "vcard",
+            ContactFormat::MeCard => "mecard",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContactFormat::VCard => "vCard 3.0",
+            ContactFormat::MeCard => "MeCard",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.code() == code)
+    }
+}
+
+/// Fiche contact (carte de visite)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContactPayload {
+    pub first_name: String,
+    pub last_name: String,
+    pub organisation: String,
+    pub title: String,
+    pub phones: Vec<String>,
+    pub emails: Vec<String>,
+    pub url: String,
+    pub address: String,
+    pub note: String,
+    pub format: ContactFormat,
+}
+
+/// Contenu d'un QR code : texte libre ou données structurées
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QrContent {
+    /// Texte libre (le champ `text` du QR code fait foi)
+    #[default]
+    Text,
+    Wifi(WifiPayload),
+    Contact(ContactPayload),
+}
+
+impl QrContent {
+    /// Code court utilisé dans les formulaires
+    pub fn code(&self) -> &'static str {
+        match self {
+            QrContent::Text => "text",
+            QrContent::Wifi(_) => "wifi",
+            QrContent::Contact(_) => "contact",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QrContent::Text => "Texte ou URL",
+            QrContent::Wifi(_) => "Wi-Fi",
+            QrContent::Contact(_) => "Contact",
+        }
+    }
+}
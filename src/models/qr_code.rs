@@ -1,7 +1,75 @@
 //! Modèles de données pour les QR codes
 
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+use super::payload::QrContent;
+use crate::config::UiSettings;
+
+/// Identifiant validé d'un QR code sauvegardé, de la forme `qr-<u64>`
+///
+/// Sert aussi de nom de fichier : toute autre valeur (`../`, séparateurs, …) est refusée.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QrId(String);
+
+impl QrId {
+    const PREFIX: &'static str = "qr-";
+
+    /// Nouvel identifiant aléatoire
+    pub fn generate() -> Self {
+        QrId(format!("{}{}", Self::PREFIX, fastrand::u64(..)))
+    }
+
+    /// Valide un identifiant reçu du client (forme canonique `qr-<u64>` uniquement)
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Identifiant de QR code invalide : {:?}", value);
+        let digits = value.strip_prefix(Self::PREFIX).ok_or_else(invalid)?;
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let number: u64 = digits.parse().map_err(|_| invalid())?;
+        if number.to_string() != digits {
+            return Err(invalid());
+        }
+        Ok(QrId(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for QrId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for QrId {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        QrId::parse(value)
+    }
+}
+
+impl TryFrom<String> for QrId {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        QrId::parse(&value)
+    }
+}
+
+impl From<QrId> for String {
+    fn from(id: QrId) -> Self {
+        id.0
+    }
+}
+
 /// Activer/désactiver la marge autour du QR code
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MarginEnabled(pub bool);
@@ -12,24 +80,547 @@ impl Default for MarginEnabled {
     }
 }
 
+/// Couleur RGBA, sérialisée en hexadécimal (`#RRGGBBAA`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QrColor(pub [u8; 4]);
+
+impl QrColor {
+    pub const BLACK: QrColor = QrColor([0, 0, 0, 255]);
+    pub const WHITE: QrColor = QrColor([255, 255, 255, 255]);
+
+    /// Analyse une couleur `#RGB`, `#RRGGBB` ou `#RRGGBBAA` (le `#` est optionnel)
+    pub fn parse_hex(value: &str) -> Option<Self> {
+        let hex = value.trim().trim_start_matches('#');
+        if !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        match hex.len() {
+            3 => {
+                let mut rgba = [255u8; 4];
+                for (i, c) in hex.chars().enumerate() {
+                    let v = c.to_digit(16)? as u8;
+                    rgba[i] = v * 16 + v;
+                }
+                Some(QrColor(rgba))
+            }
+            6 => Some(QrColor([channel(0)?, channel(2)?, channel(4)?, 255])),
+            8 => Some(QrColor([
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)?,
+            ])),
+            _ => None,
+        }
+    }
+
+    /// Représentation `#RRGGBBAA`
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.0;
+        format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    }
+
+    /// Représentation `#RRGGBB` attendue par `<input type="color">`
+    pub fn to_rgb_hex(&self) -> String {
+        let [r, g, b, _] = self.0;
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
+    /// Remplace les composantes RVB en conservant l'opacité
+    pub fn with_rgb_hex(&self, value: &str) -> Option<Self> {
+        let QrColor([r, g, b, _]) = Self::parse_hex(value)?;
+        Some(QrColor([r, g, b, self.0[3]]))
+    }
+
+    pub fn with_alpha(&self, alpha: u8) -> Self {
+        let [r, g, b, _] = self.0;
+        QrColor([r, g, b, alpha])
+    }
+}
+
+impl TryFrom<String> for QrColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        QrColor::parse_hex(&value).ok_or_else(|| format!("Couleur invalide : {}", value))
+    }
+}
+
+impl From<QrColor> for String {
+    fn from(color: QrColor) -> Self {
+        color.to_hex()
+    }
+}
+
+fn default_foreground() -> QrColor {
+    QrColor::BLACK
+}
+
+fn default_background() -> QrColor {
+    QrColor::WHITE
+}
+
+/// Niveau de correction d'erreur du QR code
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCorrection {
+    /// ~7% de redondance
+    L,
+    /// ~15% de redondance (niveau par défaut de la crate `qrcode`)
+    #[default]
+    M,
+    /// ~25% de redondance
+    Q,
+    /// ~30% de redondance, recommandé avec un logo ou une impression difficile
+    H,
+}
+
+impl ErrorCorrection {
+    pub const ALL: [ErrorCorrection; 4] = [
+        ErrorCorrection::L,
+        ErrorCorrection::M,
+        ErrorCorrection::Q,
+        ErrorCorrection::H,
+    ];
+
+    /// Code court utilisé dans les formulaires
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCorrection::L => "L",
+            ErrorCorrection::M => "M",
+            ErrorCorrection::Q => "Q",
+            ErrorCorrection::H => "H",
+        }
+    }
+
+    /// Libellé affiché dans l'interface
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorCorrection::L => "L (7%)",
+            ErrorCorrection::M => "M (15%)",
+            ErrorCorrection::Q => "Q (25%)",
+            ErrorCorrection::H => "H (30%)",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ec| ec.code() == code)
+    }
+}
+
+/// Forme des modules de données
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModuleStyle {
+    #[default]
+    Square,
+    Circle,
+    Rounded,
+    /// Modules reliés en barres verticales
+    VerticalBars,
+    /// Modules reliés en barres horizontales
+    HorizontalBars,
+}
+
+impl ModuleStyle {
+    pub const ALL: [ModuleStyle; 5] = [
+        ModuleStyle::Square,
+        ModuleStyle::Circle,
+        ModuleStyle::Rounded,
+        ModuleStyle::VerticalBars,
+        ModuleStyle::HorizontalBars,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ModuleStyle::Square => "square",
+            ModuleStyle::Circle => "circle",
+            ModuleStyle::Rounded => "rounded",
+            ModuleStyle::VerticalBars => "vertical-bars",
+            ModuleStyle::HorizontalBars => "horizontal-bars",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ModuleStyle::Square => "Carrés",
+            ModuleStyle::Circle => "Points",
+            ModuleStyle::Rounded => "Arrondis",
+            ModuleStyle::VerticalBars => "Barres verticales",
+            ModuleStyle::HorizontalBars => "Barres horizontales",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.code() == code)
+    }
+}
+
+/// Forme des trois motifs de repérage (les « yeux » aux coins du QR code)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FinderStyle {
+    #[default]
+    Square,
+    Rounded,
+    Circle,
+}
+
+impl FinderStyle {
+    pub const ALL: [FinderStyle; 3] = [
+        FinderStyle::Square,
+        FinderStyle::Rounded,
+        FinderStyle::Circle,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            FinderStyle::Square => "square",
+            FinderStyle::Rounded => "rounded",
+            FinderStyle::Circle => "circle",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FinderStyle::Square => "Carrés",
+            FinderStyle::Rounded => "Arrondis",
+            FinderStyle::Circle => "Ronds",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.code() == code)
+    }
+}
+
+/// Logo incrusté au centre du QR code
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QrLogo {
+    /// Image PNG ou JPEG encodée en base64
+    pub data: String,
+    /// Largeur maximale du logo, en pourcentage de la largeur du QR code
+    pub scale_percent: u32,
+    /// Marge du cadre de fond autour du logo, en pourcentage de la largeur du QR code
+    pub padding_percent: u32,
+}
+
+impl QrLogo {
+    pub const DEFAULT_SCALE_PERCENT: u32 = 20;
+    pub const DEFAULT_PADDING_PERCENT: u32 = 2;
+    /// Au-delà, même le niveau H ne suffit plus à reconstituer les modules masqués
+    pub const MAX_SCALE_PERCENT: u32 = 30;
+    /// Au-delà, le cadre masque trop de modules autour du logo
+    pub const MAX_PADDING_PERCENT: u32 = 5;
+
+    pub fn new(data: String) -> Self {
+        QrLogo {
+            data,
+            scale_percent: Self::DEFAULT_SCALE_PERCENT,
+            padding_percent: Self::DEFAULT_PADDING_PERCENT,
+        }
+    }
+}
+
+/// Format de fichier proposé au téléchargement
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Png, ExportFormat::Svg];
+
+    /// Extension de fichier associée au format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Svg => "svg",
+        }
+    }
+
+    /// Format correspondant à une extension de fichier (sans le point)
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+/// Paramètres de rendu communs à l'aperçu, à l'export et à la sauvegarde
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RenderOptions {
+    pub size: u32,
+    pub transparent: bool,
+    pub margin: MarginEnabled,
+    pub error_correction: ErrorCorrection,
+    pub foreground: QrColor,
+    pub background: QrColor,
+    pub logo: Option<QrLogo>,
+    pub module_style: ModuleStyle,
+    pub finder_style: FinderStyle,
+}
+
+impl RenderOptions {
+    /// Niveau de correction réellement utilisé : un logo impose le niveau H
+    pub fn effective_error_correction(&self) -> ErrorCorrection {
+        if self.logo.is_some() {
+            ErrorCorrection::H
+        } else {
+            self.error_correction
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            size: 256,
+            transparent: false,
+            margin: MarginEnabled::default(),
+            error_correction: ErrorCorrection::default(),
+            foreground: default_foreground(),
+            background: default_background(),
+            logo: None,
+            module_style: ModuleStyle::default(),
+            finder_style: FinderStyle::default(),
+        }
+    }
+}
+
+/// Aperçu généré par le serveur
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RenderedQr {
+    /// Image PNG sous forme de data URL
+    pub data_url: String,
+    /// Le rendu a été relu avec succès par un décodeur
+    pub scannable: bool,
+}
+
+/// Lien dynamique : le QR code encode une URL courte du serveur (`/r/<slug>`)
+/// qui redirige vers une cible modifiable après impression
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DynamicLink {
+    pub slug: String,
+    /// URL de destination de la redirection
+    pub target: String,
+}
+
+impl DynamicLink {
+    pub const SLUG_LENGTH: usize = 8;
+    pub const MAX_SLUG_LENGTH: usize = 64;
+
+    /// Nouveau lien avec un slug aléatoire
+    pub fn generate(target: String) -> Self {
+        let slug = std::iter::repeat_with(fastrand::alphanumeric)
+            .take(Self::SLUG_LENGTH)
+            .collect();
+        DynamicLink { slug, target }
+    }
+
+    /// Slug utilisable dans une URL : lettres, chiffres, `-` et `_`
+    pub fn is_valid_slug(slug: &str) -> bool {
+        !slug.is_empty()
+            && slug.len() <= Self::MAX_SLUG_LENGTH
+            && slug
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    }
+
+    /// URL courte encodée dans le QR code
+    pub fn short_url(&self, base_url: &str) -> String {
+        format!("{}/r/{}", base_url.trim_end_matches('/'), self.slug)
+    }
+}
+
 /// Configuration d'un QR code pour l'interface utilisateur
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Debug)]
 pub struct UIQr {
-    pub id: String,
+    pub id: QrId,
     pub text: String,
     pub size: u32,
     pub transparent: bool,
     pub margin: MarginEnabled,
+    pub error_correction: ErrorCorrection,
+    pub foreground: QrColor,
+    pub background: QrColor,
+    pub logo: Option<QrLogo>,
+    pub module_style: ModuleStyle,
+    pub finder_style: FinderStyle,
+    pub content: QrContent,
+    /// Révision enregistrée dont part l'édition (0 pour un nouveau QR code)
+    pub revision: u64,
+    pub dynamic: Option<DynamicLink>,
+}
+
+impl Default for UIQr {
+    fn default() -> Self {
+        UIQr {
+            id: QrId::generate(),
+            text: String::new(),
+            size: 0,
+            transparent: false,
+            margin: MarginEnabled::default(),
+            error_correction: ErrorCorrection::default(),
+            foreground: default_foreground(),
+            background: default_background(),
+            logo: None,
+            module_style: ModuleStyle::default(),
+            finder_style: FinderStyle::default(),
+            content: QrContent::default(),
+            revision: 0,
+            dynamic: None,
+        }
+    }
 }
 
+impl UIQr {
+    /// Nouveau QR code (identifiant aléatoire) pour le texte donné, avec la
+    /// taille et la marge par défaut de la configuration
+    pub fn new(text: String, defaults: &UiSettings) -> Self {
+        UIQr {
+            id: QrId::generate(),
+            text,
+            size: defaults.default_size,
+            margin: MarginEnabled(defaults.default_margin),
+            ..UIQr::default()
+        }
+    }
+
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            size: self.size,
+            transparent: self.transparent,
+            margin: self.margin,
+            error_correction: self.error_correction,
+            foreground: self.foreground,
+            background: self.background,
+            logo: self.logo.clone(),
+            module_style: self.module_style,
+            finder_style: self.finder_style,
+        }
+    }
+}
+
+/// Version courante du format des QR codes sauvegardés
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Représentation d'un QR code sauvegardé
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Seuls les paramètres de génération sont conservés : l'image est rendue à
+/// la demande (les anciens fichiers contenaient un champ `image_data`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SavedQr {
-    pub id: String,
+    /// Version du format (0 pour les fichiers antérieurs au versionnage)
+    #[serde(default)]
+    pub schema_version: u32,
+    pub id: QrId,
     pub text: String,
     pub size: u32,
     pub transparent: bool,
     pub margin: MarginEnabled,
+    #[serde(default)]
+    pub error_correction: ErrorCorrection,
+    #[serde(default = "default_foreground")]
+    pub foreground: QrColor,
+    #[serde(default = "default_background")]
+    pub background: QrColor,
+    #[serde(default)]
+    pub logo: Option<QrLogo>,
+    #[serde(default)]
+    pub module_style: ModuleStyle,
+    #[serde(default)]
+    pub finder_style: FinderStyle,
+    /// Données structurées d'origine (le champ `text` contient leur encodage)
+    #[serde(default)]
+    pub content: QrContent,
+    /// Incrémentée à chaque sauvegarde, pour détecter les éditions concurrentes
+    #[serde(default)]
+    pub revision: u64,
+    /// Redirection modifiable ; `text` contient alors l'URL courte
+    #[serde(default)]
+    pub dynamic: Option<DynamicLink>,
     pub created_at: String,
-    pub image_data: String,
+}
+
+impl SavedQr {
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            size: self.size,
+            transparent: self.transparent,
+            margin: self.margin,
+            error_correction: self.error_correction,
+            foreground: self.foreground,
+            background: self.background,
+            logo: self.logo.clone(),
+            module_style: self.module_style,
+            finder_style: self.finder_style,
+        }
+    }
+}
+
+/// Enregistrement illisible ou impossible à migrer
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnreadableRecord {
+    /// Fichier ou identifiant concerné
+    pub source: String,
+    pub error: String,
+}
+
+/// Contenu du stockage : les QR codes lisibles et le rapport des autres
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedList {
+    pub codes: Vec<SavedQr>,
+    pub unreadable: Vec<UnreadableRecord>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qr_id_accepts_generated_ids() {
+        let id = QrId::generate();
+        assert_eq!(QrId::parse(id.as_str()), Ok(id));
+        assert!(QrId::parse("qr-0").is_ok());
+        assert!(QrId::parse("qr-18446744073709551615").is_ok());
+    }
+
+    #[test]
+    fn test_qr_id_rejects_malicious_ids() {
+        let malicious = [
+            "",
+            "qr-",
+            "../etc/passwd",
+            "qr-1/../../etc/passwd",
+            "qr-1.json",
+            "qr-../1",
+            "qr-1\\..\\x",
+            "qr-1\0",
+            "qr- 1",
+            "qr-01",
+            "qr-+1",
+            "QR-1",
+            "qr-18446744073709551616",
+            "/data/qr-1",
+        ];
+        for id in malicious {
+            assert!(QrId::parse(id).is_err(), "{:?} should be rejected", id);
+        }
+    }
+
+    #[test]
+    fn test_saved_qr_with_malicious_id_is_rejected() {
+        let json = r#"{
+            "id": "../../etc/passwd",
+            "text": "hello",
+            "size": 256,
+            "transparent": false,
+            "margin": true,
+            "created_at": "0",
+            "image_data": ""
+        }"#;
+        assert!(serde_json::from_str::<SavedQr>(json).is_err());
+    }
 }
@@ -0,0 +1,262 @@
+//! Serveur fullstack
+//!
+//! Ajoute aux routes Dioxus la redirection des QR codes dynamiques, qui
+//! enregistre chaque scan, les images PNG servies en HTTP simple et l'API
+//! REST `/api/v1`.
+
+use std::net::SocketAddr;
+
+use axum::body::Bytes;
+use axum::extract::{ConnectInfo, Path, Query};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Json, Redirect, Response};
+use axum::routing::{any, get, post, MethodRouter};
+use dioxus::prelude::*;
+use qr_craft::config::{settings, UiSettings};
+use qr_craft::models::qr_code::RenderOptions;
+use qr_craft::services::analytics::{client_ip, geoip, now, scan_event};
+use qr_craft::services::dynamic_link::resolve;
+use qr_craft::services::image_api::{
+    etag, etag_matches, saved_id, ImageQuery, QUERY_CACHE_CONTROL, SAVED_CACHE_CONTROL,
+};
+use qr_craft::services::qr_code::render_qr_png_bytes;
+use qr_craft::services::rest_api::{self, ApiError, SaveQuery};
+use qr_craft::services::storage::{storage, QrStorage};
+use serde::Serialize;
+
+use crate::components::app::App;
+
+/// Journalise l'erreur et répond 500 sans la détailler au client.
+fn server_error(context: &str, e: String) -> Response {
+    eprintln!("Erreur {}: {}", context, e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Erreur du serveur").into_response()
+}
+
+/// `GET /r/<slug>` : redirige vers la destination courante du QR code.
+async fn redirect(
+    Path(slug): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Response {
+    let storage = match storage() {
+        Ok(storage) => storage,
+        Err(e) => return server_error("de stockage", e),
+    };
+    match resolve(storage, &slug) {
+        Ok(Some((id, target))) => {
+            let header_value = |name| headers.get(name).and_then(|value| value.to_str().ok());
+            let trusted_proxies = settings()
+                .map(|settings| settings.trusted_proxies.as_slice())
+                .unwrap_or_default();
+            // Un proxy peut ajouter sa ligne d'en-tête plutôt que compléter la liste
+            let forwarded_for = headers
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect::<Vec<_>>()
+                .join(",");
+            let ip = client_ip(peer.ip(), &forwarded_for, trusted_proxies);
+            let scan = scan_event(
+                id,
+                now(),
+                header_value(header::USER_AGENT),
+                header_value(header::REFERER),
+                Some(ip),
+                geoip(),
+            );
+            // Un scan non enregistré ne doit pas empêcher la redirection
+            if let Err(e) = storage.record_scan(&scan) {
+                eprintln!("Erreur d'enregistrement du scan {}: {}", slug, e);
+            }
+            (
+                [(header::CACHE_CONTROL, "no-store")],
+                Redirect::temporary(&target),
+            )
+                .into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "QR code inconnu").into_response(),
+        Err(e) => server_error(&format!("lors de la redirection {}", slug), e),
+    }
+}
+
+/// Rend un QR code en PNG, ou répond 304 si le client a déjà cette image.
+fn png_response(
+    headers: &HeaderMap,
+    text: &str,
+    options: &RenderOptions,
+    cache_control: &'static str,
+    render_error: impl FnOnce(String) -> Response,
+) -> Response {
+    let tag = etag(text, options);
+    let cached = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, &tag));
+    let cache_headers = [
+        (header::ETAG, tag),
+        (header::CACHE_CONTROL, cache_control.to_string()),
+    ];
+    if cached {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    match render_qr_png_bytes(text, options) {
+        Ok(bytes) => (cache_headers, [(header::CONTENT_TYPE, "image/png")], bytes).into_response(),
+        Err(e) => render_error(e),
+    }
+}
+
+/// `GET /api/qr.png?text=...&size=...&margin=...`
+async fn query_png(Query(query): Query<ImageQuery>, headers: HeaderMap) -> Response {
+    let ui = match settings() {
+        Ok(settings) => &settings.ui,
+        Err(e) => return server_error("de configuration", e),
+    };
+    match query.render_options(ui) {
+        Ok(options) => png_response(&headers, &query.text, &options, QUERY_CACHE_CONTROL, |e| {
+            (StatusCode::BAD_REQUEST, e).into_response()
+        }),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+/// `GET /api/saved/<id>.png`
+async fn saved_png(Path(file): Path<String>, headers: HeaderMap) -> Response {
+    let Ok(id) = saved_id(&file) else {
+        return (StatusCode::NOT_FOUND, "QR code inconnu").into_response();
+    };
+    let storage = match storage() {
+        Ok(storage) => storage,
+        Err(e) => return server_error("de stockage", e),
+    };
+    match storage.find(&id) {
+        Ok(Some(qr)) => png_response(
+            &headers,
+            &qr.text,
+            &qr.render_options(),
+            SAVED_CACHE_CONTROL,
+            // Enregistrement déjà validé : un échec de rendu vient du serveur
+            |e| server_error(&format!("de rendu du QR code {}", id), e),
+        ),
+        Ok(None) => (StatusCode::NOT_FOUND, "QR code inconnu").into_response(),
+        Err(e) => server_error(&format!("de lecture du QR code {}", id), e),
+    }
+}
+
+fn api_response<T: Serialize>(status: StatusCode, result: Result<T, ApiError>) -> Response {
+    match result {
+        Ok(value) => (status, Json(value)).into_response(),
+        Err(error) => {
+            let status =
+                StatusCode::from_u16(error.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            (status, Json(error.body())).into_response()
+        }
+    }
+}
+
+fn api_storage() -> Result<&'static dyn QrStorage, ApiError> {
+    storage().map_err(ApiError::internal)
+}
+
+fn api_ui() -> Result<&'static UiSettings, ApiError> {
+    settings()
+        .map(|settings| &settings.ui)
+        .map_err(ApiError::internal)
+}
+
+async fn api_list_codes() -> Response {
+    api_response(StatusCode::OK, api_storage().and_then(rest_api::list_codes))
+}
+
+async fn api_create_code(Query(query): Query<SaveQuery>, body: Bytes) -> Response {
+    let result =
+        api_storage().and_then(|storage| rest_api::create_code(storage, api_ui()?, &body, &query));
+    api_response(StatusCode::CREATED, result)
+}
+
+async fn api_get_code(Path(id): Path<String>) -> Response {
+    let result = api_storage().and_then(|storage| rest_api::get_code(storage, &id));
+    api_response(StatusCode::OK, result)
+}
+
+async fn api_update_code(
+    Path(id): Path<String>,
+    Query(query): Query<SaveQuery>,
+    body: Bytes,
+) -> Response {
+    let result = api_storage()
+        .and_then(|storage| rest_api::update_code(storage, api_ui()?, &id, &body, &query));
+    api_response(StatusCode::OK, result)
+}
+
+async fn api_delete_code(Path(id): Path<String>) -> Response {
+    match api_storage().and_then(|storage| rest_api::delete_code(storage, &id)) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(error) => api_response::<()>(StatusCode::OK, Err(error)),
+    }
+}
+
+async fn api_render(body: Bytes) -> Response {
+    let result = api_ui().and_then(|ui| rest_api::render(ui, &body));
+    api_response(StatusCode::OK, result)
+}
+
+async fn api_openapi() -> Response {
+    let result = api_ui().map(rest_api::openapi_document);
+    api_response(StatusCode::OK, result)
+}
+
+async fn api_not_found() -> Response {
+    api_response::<()>(
+        StatusCode::OK,
+        Err(ApiError::not_found("Ressource inconnue")),
+    )
+}
+
+async fn api_method_not_allowed() -> Response {
+    api_response::<()>(StatusCode::OK, Err(ApiError::method_not_allowed()))
+}
+
+/// Routes de l'API REST ; toute erreur, y compris une route ou une méthode
+/// inconnue, est renvoyée en JSON.
+fn api_routes() -> axum::Router {
+    let route = |method_router: MethodRouter| method_router.fallback(api_method_not_allowed);
+    axum::Router::new()
+        .route(
+            "/api/v1/codes",
+            route(get(api_list_codes).post(api_create_code)),
+        )
+        .route(
+            "/api/v1/codes/:id",
+            route(
+                get(api_get_code)
+                    .put(api_update_code)
+                    .delete(api_delete_code),
+            ),
+        )
+        .route("/api/v1/render", route(post(api_render)))
+        .route("/api/v1/openapi.json", route(get(api_openapi)))
+        .route("/api/v1/*path", any(api_not_found))
+}
+
+pub fn router() -> axum::Router {
+    axum::Router::new()
+        .route("/r/:slug", get(redirect))
+        .route("/api/qr.png", get(query_png))
+        .route("/api/saved/:file", get(saved_png))
+        .merge(api_routes())
+        .serve_dioxus_application(ServeConfigBuilder::default(), App)
+}
+
+#[tokio::main]
+pub async fn serve() {
+    let address = dioxus::cli_config::fullstack_address_or_localhost();
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .expect("Impossible d'ouvrir le port du serveur");
+    axum::serve(
+        listener,
+        router().into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("Le serveur s'est arrêté sur une erreur");
+}
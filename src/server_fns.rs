@@ -0,0 +1,216 @@
+//! Fonctions serveur appelées par l'interface
+//!
+//! Dioxus les expose au client sous forme de requêtes HTTP ; leur corps
+//! s'appuie sur les services de la bibliothèque `qr_craft`. En mode hors
+//! ligne (fonctionnalité `offline`, incompatible avec `server`), ce sont de
+//! simples fonctions exécutées dans le navigateur, sur le stockage
+//! `localStorage`.
+
+use dioxus::prelude::*;
+#[allow(unused_imports)]
+use qr_craft::config::settings;
+use qr_craft::config::UiSettings;
+use qr_craft::models::analytics::ScanStats;
+use qr_craft::models::batch::BatchReport;
+use qr_craft::models::labels::LabelSheet;
+#[allow(unused_imports)]
+use qr_craft::models::qr_code::QrId;
+use qr_craft::models::qr_code::{RenderOptions, RenderedQr, SavedList, SavedQr};
+#[allow(unused_imports)]
+use qr_craft::services::analytics::{scans_csv, summarize};
+#[allow(unused_imports)]
+use qr_craft::services::batch::render_batch;
+#[allow(unused_imports)]
+use qr_craft::services::dynamic_link::check_dynamic;
+#[allow(unused_imports)]
+use qr_craft::services::export::select_saved;
+#[allow(unused_imports)]
+use qr_craft::services::labels::render_labels;
+#[allow(unused_imports)]
+use qr_craft::services::qr_code::{
+    check_logo, check_scannable, decode_qr_codes, render_preview, render_qr_svg,
+};
+#[allow(unused_imports)]
+use qr_craft::services::storage::{storage, QrStorage};
+#[allow(unused_imports)]
+use qr_craft::services::thumbnail::thumbnails;
+
+#[cfg_attr(not(feature = "offline"), server(GetUiSettings))]
+pub async fn get_ui_settings() -> Result<UiSettings, ServerFnError> {
+    let settings = settings().map_err(ServerFnError::new)?;
+    Ok(settings.ui.clone())
+}
+
+#[cfg_attr(not(feature = "offline"), server(GenerateQrCode))]
+pub async fn generate_qr_code(
+    text: String,
+    options: RenderOptions,
+) -> Result<RenderedQr, ServerFnError> {
+    check_request(&text, options.size).map_err(ServerFnError::new)?;
+    render_preview(&text, &options).map_err(ServerFnError::new)
+}
+
+#[cfg_attr(not(feature = "offline"), server(GenerateQrSvg))]
+pub async fn generate_qr_svg(
+    text: String,
+    options: RenderOptions,
+) -> Result<String, ServerFnError> {
+    check_request(&text, options.size).map_err(ServerFnError::new)?;
+    let svg = render_qr_svg(&text, &options).map_err(ServerFnError::new)?;
+    let base64_image = base64::encode(svg.as_bytes());
+    let data_url = format!("data:image/svg+xml;base64,{}", base64_image);
+    Ok(data_url)
+}
+
+#[cfg_attr(not(feature = "offline"), server(DecodeQrImage))]
+pub async fn decode_qr_image(data: String) -> Result<Vec<String>, ServerFnError> {
+    let bytes = base64::decode(data.trim()).map_err(|e| ServerFnError::new(e.to_string()))?;
+    run_blocking(move || decode_qr_codes(&bytes)).await
+}
+
+#[cfg_attr(not(feature = "offline"), server(SaveQr))]
+pub async fn save_qr(qr: SavedQr, force: bool) -> Result<u64, ServerFnError> {
+    check_request(&qr.text, qr.size).map_err(ServerFnError::new)?;
+    let public_url = settings()
+        .map_err(ServerFnError::new)?
+        .ui
+        .public_url
+        .clone();
+    check_dynamic(&qr, &public_url).map_err(ServerFnError::new)?;
+    if let Some(logo) = &qr.logo {
+        check_logo(logo).map_err(ServerFnError::new)?;
+    }
+    let (text, options) = (qr.text.clone(), qr.render_options());
+    run_blocking(move || check_scannable(&text, &options, force)).await?;
+    let storage = storage().map_err(ServerFnError::new)?;
+    let revision = storage.save(&qr).map_err(ServerFnError::new)?;
+    thumbnails().invalidate(&qr.id);
+    Ok(revision)
+}
+
+#[cfg_attr(not(feature = "offline"), server(ListSaved))]
+pub async fn list_saved() -> Result<SavedList, ServerFnError> {
+    let storage = storage().map_err(ServerFnError::new)?;
+    storage.list().map_err(ServerFnError::new)
+}
+
+/// Point d'accès HTTP sans usage dans l'interface : absent du mode hors ligne.
+#[cfg(not(feature = "offline"))]
+#[server(LoadSaved)]
+pub async fn load_saved(id: String) -> Result<SavedQr, ServerFnError> {
+    let id = QrId::parse(&id).map_err(ServerFnError::new)?;
+    let storage = storage().map_err(ServerFnError::new)?;
+    storage.load(&id).map_err(ServerFnError::new)
+}
+
+#[cfg_attr(not(feature = "offline"), server(DeleteSaved))]
+pub async fn delete_saved(id: String) -> Result<(), ServerFnError> {
+    let id = QrId::parse(&id).map_err(ServerFnError::new)?;
+    let storage = storage().map_err(ServerFnError::new)?;
+    storage.delete(&id).map_err(ServerFnError::new)?;
+    thumbnails().invalidate(&id);
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "offline"), server(SavedThumbnail))]
+pub async fn saved_thumbnail(id: String) -> Result<String, ServerFnError> {
+    let id = QrId::parse(&id).map_err(ServerFnError::new)?;
+    let storage = storage().map_err(ServerFnError::new)?;
+    let qr = storage.load(&id).map_err(ServerFnError::new)?;
+    thumbnails().get_or_render(&qr).map_err(ServerFnError::new)
+}
+
+/// Statistiques de scan de chaque QR code dynamique.
+#[cfg_attr(not(feature = "offline"), server(ScanStatistics))]
+pub async fn scan_stats() -> Result<Vec<ScanStats>, ServerFnError> {
+    let storage = storage().map_err(ServerFnError::new)?;
+    let list = storage.list().map_err(ServerFnError::new)?;
+    let mut stats = Vec::new();
+    for qr in list.codes.iter().filter(|qr| qr.dynamic.is_some()) {
+        let scans = storage.scans(&qr.id).map_err(ServerFnError::new)?;
+        stats.push(summarize(qr, &scans));
+    }
+    Ok(stats)
+}
+
+/// Export CSV des scans d'un QR code, ou de tous les QR codes dynamiques,
+/// sous forme de data URL.
+#[cfg_attr(not(feature = "offline"), server(ExportScans))]
+pub async fn export_scans(id: Option<String>) -> Result<String, ServerFnError> {
+    let storage = storage().map_err(ServerFnError::new)?;
+    let ids = match id {
+        Some(id) => vec![QrId::parse(&id).map_err(ServerFnError::new)?],
+        None => storage
+            .list()
+            .map_err(ServerFnError::new)?
+            .codes
+            .into_iter()
+            .filter(|qr| qr.dynamic.is_some())
+            .map(|qr| qr.id)
+            .collect(),
+    };
+    let mut scans = Vec::new();
+    for id in &ids {
+        scans.extend(storage.scans(id).map_err(ServerFnError::new)?);
+    }
+    Ok(format!(
+        "data:text/csv;charset=utf-8;base64,{}",
+        base64::encode(scans_csv(&scans))
+    ))
+}
+
+/// Génère un lot de QR codes à partir d'un fichier CSV ; l'archive ZIP est
+/// renvoyée sous forme de data URL.
+#[cfg_attr(not(feature = "offline"), server(GenerateBatch))]
+pub async fn generate_batch(csv: String) -> Result<BatchReport, ServerFnError> {
+    let settings = settings().map_err(ServerFnError::new)?;
+    let batch = run_blocking(move || render_batch(&csv, &settings.ui)).await?;
+    Ok(BatchReport {
+        rows: batch.rows,
+        archive: format!(
+            "data:application/zip;base64,{}",
+            base64::encode(batch.archive)
+        ),
+    })
+}
+
+/// Planche d'étiquettes PDF des QR codes demandés (au moins un), sous forme
+/// de data URL.
+#[cfg_attr(not(feature = "offline"), server(ExportLabels))]
+pub async fn export_labels(ids: Vec<String>, sheet: LabelSheet) -> Result<String, ServerFnError> {
+    if ids.is_empty() {
+        return Err(ServerFnError::new("Aucun QR code sélectionné"));
+    }
+    let ids = ids
+        .iter()
+        .map(|id| QrId::parse(id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ServerFnError::new)?;
+    let storage = storage().map_err(ServerFnError::new)?;
+    let pdf = run_blocking(move || render_labels(&select_saved(storage, &ids)?, &sheet)).await?;
+    Ok(format!(
+        "data:application/pdf;base64,{}",
+        base64::encode(pdf)
+    ))
+}
+
+/// Vérifie le contenu et la taille demandés au regard de la configuration.
+#[allow(dead_code)]
+fn check_request(text: &str, size: u32) -> Result<(), String> {
+    settings()?.ui.check_request(text, size)
+}
+
+/// Exécute un rendu long sur un thread dédié du serveur, pour ne pas bloquer
+/// les autres requêtes ; directement en mode hors ligne.
+#[allow(dead_code)]
+async fn run_blocking<T: Send + 'static>(
+    render: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, ServerFnError> {
+    #[cfg(feature = "server")]
+    let result = tokio::task::spawn_blocking(render)
+        .await
+        .map_err(ServerFnError::new)?;
+    #[cfg(not(feature = "server"))]
+    let result = render();
+    result.map_err(ServerFnError::new)
+}
@@ -0,0 +1,432 @@
+//! Statistiques de scan des QR codes dynamiques
+//!
+//! Chaque passage par `/r/<slug>` est enregistré par le stockage sous forme
+//! de [`ScanEvent`] : catégorie d'appareil, domaine d'origine et pays,
+//! déduit de l'adresse IP via un fichier GeoIP local s'il est configuré.
+//! Le client n'utilise que la mise en forme des dates.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::settings;
+use crate::models::analytics::{AgentClass, DailyScans, ScanEvent, ScanStats};
+use crate::models::qr_code::{QrId, SavedQr};
+use crate::services::csv::csv_field;
+
+/// Classe un User-Agent ; les robots sont testés en premier car beaucoup
+/// imitent un navigateur mobile.
+pub fn classify_agent(user_agent: &str) -> AgentClass {
+    let ua = user_agent.to_ascii_lowercase();
+    const BOTS: [&str; 8] = [
+        "bot",
+        "crawler",
+        "spider",
+        "preview",
+        "curl",
+        "wget",
+        "python-requests",
+        "go-http-client",
+    ];
+    const MOBILES: [&str; 5] = ["mobile", "android", "iphone", "ipad", "ipod"];
+    const DESKTOPS: [&str; 4] = ["windows", "macintosh", "x11", "cros"];
+    if ua.is_empty() {
+        AgentClass::Other
+    } else if BOTS.iter().any(|bot| ua.contains(bot)) {
+        AgentClass::Bot
+    } else if MOBILES.iter().any(|mobile| ua.contains(mobile)) {
+        AgentClass::Mobile
+    } else if DESKTOPS.iter().any(|desktop| ua.contains(desktop)) {
+        AgentClass::Desktop
+    } else {
+        AgentClass::Other
+    }
+}
+
+/// Domaine d'un en-tête `Referer` ; le chemin et la requête ne sont pas conservés.
+pub fn referrer_host(referer: &str) -> Option<String> {
+    let (_, rest) = referer.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next()?,
+        None => host.split(':').next()?,
+    };
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Table de correspondance IP → pays lue depuis un CSV de plages
+/// `debut,fin,pays` (format « country lite » de DB-IP, IPv4 et IPv6).
+pub struct GeoIp {
+    /// Plages triées par adresse de début
+    ranges: Vec<(u128, u128, String)>,
+}
+
+/// Adresse sur 128 bits, les IPv4 étant projetées dans `::ffff:0:0/96`.
+fn ip_key(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(ip) => u128::from(ip.to_ipv6_mapped()),
+        IpAddr::V6(ip) => u128::from(ip),
+    }
+}
+
+impl GeoIp {
+    pub fn parse(csv: &str) -> Result<Self, String> {
+        let mut ranges = Vec::new();
+        for (n, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(',')
+                .map(|f| f.trim().trim_matches('"'))
+                .collect();
+            let [start, end, country, ..] = fields[..] else {
+                return Err(format!("GeoIP ligne {} : 3 colonnes attendues", n + 1));
+            };
+            let parse = |ip: &str| {
+                ip.parse::<IpAddr>()
+                    .map(ip_key)
+                    .map_err(|_| format!("GeoIP ligne {} : adresse invalide {:?}", n + 1, ip))
+            };
+            let (start, end) = (parse(start)?, parse(end)?);
+            if start > end || country.len() != 2 {
+                return Err(format!("GeoIP ligne {} : plage invalide", n + 1));
+            }
+            ranges.push((start, end, country.to_ascii_uppercase()));
+        }
+        ranges.sort_by_key(|range| range.0);
+        Ok(Self { ranges })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let csv = std::fs::read_to_string(path).map_err(|e| format!("{:?} : {}", path, e))?;
+        Self::parse(&csv)
+    }
+
+    /// Pays de l'adresse ; `ZZ` dans le fichier signifie « inconnu ».
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let key = ip_key(ip);
+        let index = self.ranges.partition_point(|range| range.0 <= key);
+        let (_, end, country) = self.ranges.get(index.checked_sub(1)?)?;
+        (key <= *end && country != "ZZ").then(|| country.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+static GEOIP: OnceLock<Option<GeoIp>> = OnceLock::new();
+
+/// Table GeoIP configurée, chargée au premier scan ; une erreur de
+/// chargement désactive la géolocalisation sans bloquer les redirections.
+pub fn geoip() -> Option<&'static GeoIp> {
+    GEOIP
+        .get_or_init(|| {
+            let path = settings().ok()?.geoip_file.as_ref()?;
+            GeoIp::load(path)
+                .map_err(|e| eprintln!("Erreur de chargement GeoIP: {}", e))
+                .ok()
+        })
+        .as_ref()
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Adresse du client. `X-Forwarded-For` (vide s'il est absent) n'est lu que
+/// si la connexion vient d'un proxy de confiance : l'en-tête est parcouru
+/// depuis la fin (les proxys y ajoutent l'adresse de leur client) jusqu'à la
+/// première adresse qui n'est pas celle d'un proxy de confiance.
+pub fn client_ip(peer: IpAddr, forwarded_for: &str, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+    for hop in forwarded_for.rsplit(',') {
+        match hop.trim().parse() {
+            Ok(ip) if trusted_proxies.contains(&ip) => continue,
+            Ok(ip) => return ip,
+            Err(_) => break,
+        }
+    }
+    peer
+}
+
+/// Construit l'événement d'un scan à partir des informations de la requête.
+pub fn scan_event(
+    qr_id: QrId,
+    at: u64,
+    user_agent: Option<&str>,
+    referer: Option<&str>,
+    ip: Option<IpAddr>,
+    geoip: Option<&GeoIp>,
+) -> ScanEvent {
+    ScanEvent {
+        qr_id,
+        at,
+        agent: classify_agent(user_agent.unwrap_or_default()),
+        referrer: referer.and_then(referrer_host),
+        country: ip.zip(geoip).and_then(|(ip, geoip)| geoip.country(ip)),
+    }
+}
+
+/// Date UTC `AAAA-MM-JJ` d'un horodatage Unix.
+pub fn day(at: u64) -> String {
+    // Algorithme « civil_from_days » de Howard Hinnant
+    let z = (at / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// Horodatage ISO 8601 (UTC) pour l'export.
+fn timestamp(at: u64) -> String {
+    let secs = at % 86_400;
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        day(at),
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+/// Agrège les scans d'un QR code dynamique.
+pub fn summarize(qr: &SavedQr, scans: &[ScanEvent]) -> ScanStats {
+    let mut per_day = BTreeMap::<String, u64>::new();
+    let mut per_agent = HashMap::<AgentClass, u64>::new();
+    for scan in scans {
+        *per_day.entry(day(scan.at)).or_default() += 1;
+        *per_agent.entry(scan.agent).or_default() += 1;
+    }
+    let link = qr.dynamic.clone().unwrap_or_default();
+    ScanStats {
+        id: qr.id.clone(),
+        slug: link.slug,
+        target: link.target,
+        total: scans.len() as u64,
+        per_day: per_day
+            .into_iter()
+            .map(|(day, count)| DailyScans { day, count })
+            .collect(),
+        per_agent: AgentClass::ALL
+            .into_iter()
+            .filter_map(|agent| per_agent.get(&agent).map(|count| (agent, *count)))
+            .collect(),
+        last_scan: scans.iter().map(|scan| scan.at).max(),
+    }
+}
+
+/// Export CSV des scans, une ligne par scan.
+pub fn scans_csv(scans: &[ScanEvent]) -> String {
+    let mut csv = String::from("qr_id,timestamp,agent,referrer,country\r\n");
+    for scan in scans {
+        let fields = [
+            scan.qr_id.to_string(),
+            timestamp(scan.at),
+            scan.agent.code().to_string(),
+            scan.referrer.clone().unwrap_or_default(),
+            scan.country.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::qr_code::DynamicLink;
+
+    fn scan(at: u64, agent: AgentClass) -> ScanEvent {
+        ScanEvent {
+            qr_id: QrId::parse("qr-1").unwrap(),
+            at,
+            agent,
+            referrer: None,
+            country: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_agent() {
+        let iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148";
+        let android = "Mozilla/5.0 (Linux; Android 14; Pixel 8) Chrome/120 Mobile Safari/537.36";
+        let windows = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/120 Safari/537.36";
+        let google = "Mozilla/5.0 (Linux; Android 6.0.1) Mobile (compatible; Googlebot/2.1)";
+        assert_eq!(classify_agent(iphone), AgentClass::Mobile);
+        assert_eq!(classify_agent(android), AgentClass::Mobile);
+        assert_eq!(classify_agent(windows), AgentClass::Desktop);
+        assert_eq!(classify_agent(google), AgentClass::Bot);
+        assert_eq!(classify_agent("curl/8.4.0"), AgentClass::Bot);
+        assert_eq!(classify_agent(""), AgentClass::Other);
+    }
+
+    #[test]
+    fn test_referrer_host() {
+        assert_eq!(
+            referrer_host("https://www.Example.com:8443/page?q=1").as_deref(),
+            Some("www.example.com")
+        );
+        assert_eq!(
+            referrer_host("http://user@example.org/").as_deref(),
+            Some("example.org")
+        );
+        assert_eq!(
+            referrer_host("android-app://com.app"),
+            Some("com.app".into())
+        );
+        assert_eq!(referrer_host("pas une url"), None);
+        assert_eq!(referrer_host("https:///chemin"), None);
+    }
+
+    #[test]
+    fn test_geoip_lookup() {
+        let geoip = GeoIp::parse(
+            "1.0.0.0,1.0.0.255,AU\n\
+             \"2.0.0.0\",\"2.255.255.255\",\"fr\"\n\
+             3.0.0.0,3.0.0.255,ZZ\n\
+             2001:db8::,2001:db8::ffff,DE\n",
+        )
+        .unwrap();
+        assert_eq!(geoip.len(), 4);
+        let country = |ip: &str| geoip.country(ip.parse().unwrap());
+        assert_eq!(country("1.0.0.42").as_deref(), Some("AU"));
+        assert_eq!(country("2.12.34.56").as_deref(), Some("FR"));
+        assert_eq!(country("2001:db8::1").as_deref(), Some("DE"));
+        assert_eq!(country("1.0.1.0"), None);
+        assert_eq!(country("0.0.0.1"), None);
+        assert_eq!(country("3.0.0.1"), None);
+
+        assert!(GeoIp::parse("1.0.0.0,AU").is_err());
+        assert!(GeoIp::parse("1.0.0.9,1.0.0.0,AU").is_err());
+        assert!(GeoIp::parse("x,1.0.0.0,AU").is_err());
+    }
+
+    #[test]
+    fn test_scan_event() {
+        let geoip = GeoIp::parse("2.0.0.0,2.255.255.255,FR").unwrap();
+        let event = scan_event(
+            QrId::parse("qr-1").unwrap(),
+            0,
+            Some("Mozilla/5.0 (iPhone)"),
+            Some("https://news.example.com/article/42"),
+            Some("2.3.4.5".parse().unwrap()),
+            Some(&geoip),
+        );
+        assert_eq!(event.agent, AgentClass::Mobile);
+        assert_eq!(event.referrer.as_deref(), Some("news.example.com"));
+        assert_eq!(event.country.as_deref(), Some("FR"));
+
+        let anonymous = scan_event(QrId::parse("qr-1").unwrap(), 0, None, None, None, None);
+        assert_eq!(anonymous.agent, AgentClass::Other);
+        assert_eq!(anonymous.country, None);
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let ip = |ip: &str| -> IpAddr { ip.parse().unwrap() };
+        let proxies = [ip("10.0.0.1"), ip("10.0.0.2")];
+        // Sans proxy de confiance, l'en-tête est ignoré
+        assert_eq!(client_ip(ip("2.3.4.5"), "1.1.1.1", &[]), ip("2.3.4.5"));
+        assert_eq!(client_ip(ip("2.3.4.5"), "1.1.1.1", &proxies), ip("2.3.4.5"));
+        // Derrière les proxys, l'adresse ajoutée par le dernier d'entre eux
+        // l'emporte sur celle envoyée par le client
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), "1.1.1.1, 2.3.4.5, 10.0.0.2", &proxies),
+            ip("2.3.4.5")
+        );
+        assert_eq!(client_ip(ip("10.0.0.1"), "", &proxies), ip("10.0.0.1"));
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), "inconnu", &proxies),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_day() {
+        assert_eq!(day(0), "1970-01-01");
+        assert_eq!(day(951_782_400), "2000-02-29");
+        assert_eq!(day(1_709_251_199), "2024-02-29");
+        assert_eq!(day(1_709_251_200), "2024-03-01");
+        assert_eq!(timestamp(1_709_251_199), "2024-02-29T23:59:59Z");
+    }
+
+    #[test]
+    fn test_summarize() {
+        let mut qr: SavedQr = serde_json::from_value(serde_json::json!({
+            "id": "qr-1",
+            "text": "https://qr.example.com/r/promo",
+            "size": 256,
+            "transparent": false,
+            "margin": true,
+            "created_at": "0",
+        }))
+        .unwrap();
+        qr.dynamic = Some(DynamicLink {
+            slug: "promo".into(),
+            target: "https://example.com".into(),
+        });
+        let scans = [
+            scan(1_709_251_200 + 10, AgentClass::Mobile),
+            scan(1_709_251_199, AgentClass::Mobile),
+            scan(1_709_251_200, AgentClass::Desktop),
+        ];
+        let stats = summarize(&qr, &scans);
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.slug, "promo");
+        assert_eq!(
+            stats.per_day,
+            vec![
+                DailyScans {
+                    day: "2024-02-29".into(),
+                    count: 1
+                },
+                DailyScans {
+                    day: "2024-03-01".into(),
+                    count: 2
+                },
+            ]
+        );
+        assert_eq!(
+            stats.per_agent,
+            vec![(AgentClass::Mobile, 2), (AgentClass::Desktop, 1)]
+        );
+        assert_eq!(stats.last_scan, Some(1_709_251_210));
+        assert_eq!(summarize(&qr, &[]).total, 0);
+    }
+
+    #[test]
+    fn test_scans_csv() {
+        let mut event = scan(0, AgentClass::Bot);
+        event.referrer = Some("a,\"b\"".into());
+        event.country = Some("=FR".into());
+        let csv = scans_csv(&[event]);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("qr_id,timestamp,agent,referrer,country"));
+        assert_eq!(
+            lines.next(),
+            Some("qr-1,1970-01-01T00:00:00Z,bot,\"a,\"\"b\"\"\",'=FR")
+        );
+        assert_eq!(lines.next(), None);
+    }
+}
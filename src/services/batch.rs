@@ -0,0 +1,442 @@
+//! Génération par lot à partir d'un fichier CSV
+//!
+//! Chaque ligne décrit un QR code : un texte (colonne `text`) ou un contenu
+//! structuré (colonne `type` et champs Wi-Fi ou contact), un nom de fichier
+//! et, au besoin, des options de rendu propres à la ligne. Les images sont
+//! rassemblées dans une archive ZIP avec un manifeste CSV ; une ligne
+//! invalide est signalée sans interrompre le lot.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Write};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::config::UiSettings;
+use crate::models::batch::BatchRow;
+use crate::models::payload::{ContactFormat, ContactPayload, QrContent, WifiPayload, WifiSecurity};
+use crate::models::qr_code::{
+    ErrorCorrection, FinderStyle, MarginEnabled, ModuleStyle, QrColor, RenderOptions, UIQr,
+};
+use crate::services::csv::{csv_field, parse_csv};
+use crate::services::payload::{content_summary, encode_content};
+use crate::services::qr_code::render_qr_png_bytes;
+
+/// Nombre maximal de QR codes par lot
+pub const MAX_BATCH_ROWS: usize = 500;
+
+/// Nom du manifeste dans l'archive
+pub const MANIFEST_NAME: &str = "manifest.csv";
+
+/// Colonnes reconnues dans l'en-tête
+pub const COLUMNS: [&str; 25] = [
+    "type",
+    "text",
+    "filename",
+    "ssid",
+    "password",
+    "security",
+    "hidden",
+    "first_name",
+    "last_name",
+    "organisation",
+    "title",
+    "phone",
+    "email",
+    "url",
+    "address",
+    "note",
+    "card",
+    "size",
+    "margin",
+    "transparent",
+    "foreground",
+    "background",
+    "error_correction",
+    "module_style",
+    "finder_style",
+];
+
+/// Lot rendu : compte rendu par ligne et archive ZIP
+#[derive(Clone, Debug, Default)]
+pub struct Batch {
+    pub rows: Vec<BatchRow>,
+    pub archive: Vec<u8>,
+}
+
+/// Valeurs d'une ligne, indexées par nom de colonne
+struct Record<'a> {
+    fields: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> Record<'a> {
+    fn get(&self, column: &str) -> &'a str {
+        self.fields
+            .get(column)
+            .copied()
+            .map(str::trim)
+            .unwrap_or("")
+    }
+
+    /// Valeur non vide de la colonne, à interpréter avec `parse`
+    fn parse<T>(
+        &self,
+        column: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<Option<T>, String> {
+        match self.get(column) {
+            "" => Ok(None),
+            value => parse(value)
+                .map(Some)
+                .ok_or_else(|| format!("Valeur invalide pour {} : {}", column, value)),
+        }
+    }
+
+    /// Valeurs multiples d'une colonne, séparées par `|`
+    fn list(&self, column: &str) -> Vec<String> {
+        self.get(column)
+            .split('|')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(String::from)
+            .collect()
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "oui" | "yes" => Some(true),
+        "0" | "false" | "non" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Contenu décrit par la ligne et texte à encoder
+fn row_content(record: &Record) -> Result<(QrContent, String), String> {
+    let content = match record.get("type").to_ascii_lowercase().as_str() {
+        "" | "text" => QrContent::Text,
+        "wifi" => QrContent::Wifi(WifiPayload {
+            ssid: record.get("ssid").to_string(),
+            security: record
+                .parse("security", |value| {
+                    WifiSecurity::ALL
+                        .into_iter()
+                        .find(|security| security.code().eq_ignore_ascii_case(value))
+                })?
+                .unwrap_or_default(),
+            password: record.get("password").to_string(),
+            hidden: record.parse("hidden", parse_bool)?.unwrap_or(false),
+        }),
+        "contact" => QrContent::Contact(ContactPayload {
+            first_name: record.get("first_name").to_string(),
+            last_name: record.get("last_name").to_string(),
+            organisation: record.get("organisation").to_string(),
+            title: record.get("title").to_string(),
+            phones: record.list("phone"),
+            emails: record.list("email"),
+            url: record.get("url").to_string(),
+            address: record.get("address").to_string(),
+            note: record.get("note").to_string(),
+            format: record
+                .parse("card", |value| {
+                    ContactFormat::from_code(&value.to_ascii_lowercase())
+                })?
+                .unwrap_or_default(),
+        }),
+        other => return Err(format!("Type de contenu inconnu : {}", other)),
+    };
+    let text = match encode_content(&content) {
+        Some(text) => text?,
+        None => record.get("text").to_string(),
+    };
+    if text.is_empty() {
+        return Err("Contenu vide".into());
+    }
+    Ok((content, text))
+}
+
+/// Options de rendu de la ligne : celles de `base`, surchargées par les
+/// colonnes renseignées
+fn row_options(record: &Record, base: &RenderOptions) -> Result<RenderOptions, String> {
+    let color = |value: &str| QrColor::try_from(value.to_string()).ok();
+    Ok(RenderOptions {
+        size: record
+            .parse("size", |value| value.parse().ok())?
+            .unwrap_or(base.size),
+        transparent: record
+            .parse("transparent", parse_bool)?
+            .unwrap_or(base.transparent),
+        margin: record
+            .parse("margin", parse_bool)?
+            .map(MarginEnabled)
+            .unwrap_or(base.margin),
+        error_correction: record
+            .parse("error_correction", |value| {
+                ErrorCorrection::from_code(&value.to_ascii_uppercase())
+            })?
+            .unwrap_or(base.error_correction),
+        foreground: record
+            .parse("foreground", color)?
+            .unwrap_or(base.foreground),
+        background: record
+            .parse("background", color)?
+            .unwrap_or(base.background),
+        logo: base.logo.clone(),
+        module_style: record
+            .parse("module_style", ModuleStyle::from_code)?
+            .unwrap_or(base.module_style),
+        finder_style: record
+            .parse("finder_style", FinderStyle::from_code)?
+            .unwrap_or(base.finder_style),
+    })
+}
+
+/// Nom de fichier sûr et unique dans l'archive, avec l'extension `.png`
+fn file_name(requested: &str, line: usize, used: &mut HashSet<String>) -> String {
+    let stem = requested
+        .strip_suffix(".png")
+        .or_else(|| requested.strip_suffix(".PNG"))
+        .unwrap_or(requested);
+    let stem: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let stem = match stem.trim_start_matches('.') {
+        "" => format!("qr-{}", line),
+        stem => stem.to_string(),
+    };
+    let mut name = format!("{}.png", stem);
+    let mut n = 2;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{}-{}.png", stem, n);
+        n += 1;
+    }
+    name
+}
+
+/// Rend chaque ligne du fichier CSV et produit l'archive ZIP.
+///
+/// Les lignes héritent de la taille et de la marge par défaut de la
+/// configuration ; chaque rendu est soumis aux mêmes limites que l'interface.
+pub fn render_batch(csv: &str, ui: &UiSettings) -> Result<Batch, String> {
+    let mut lines = parse_csv(csv)?.into_iter();
+    let (_, header) = lines.next().ok_or("Le fichier CSV est vide")?;
+    let header: Vec<String> = header
+        .iter()
+        .map(|column| column.trim().to_ascii_lowercase())
+        .collect();
+    if let Some(column) = header
+        .iter()
+        .find(|column| !COLUMNS.contains(&column.as_str()))
+    {
+        return Err(format!("Colonne inconnue : {}", column));
+    }
+    if !header
+        .iter()
+        .any(|column| column == "text" || column == "type")
+    {
+        return Err("Le fichier CSV doit contenir une colonne text ou type".into());
+    }
+    let lines: Vec<_> = lines.collect();
+    if lines.len() > MAX_BATCH_ROWS {
+        return Err(format!(
+            "Trop de lignes : {} (au plus {})",
+            lines.len(),
+            MAX_BATCH_ROWS
+        ));
+    }
+
+    let base = UIQr::new(String::new(), ui).render_options();
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut used = HashSet::new();
+    let mut manifest = String::from("line,file,content,status,error\r\n");
+    let mut rows = Vec::with_capacity(lines.len());
+    for (line, values) in &lines {
+        let record = Record {
+            fields: header
+                .iter()
+                .map(String::as_str)
+                .zip(values.iter().map(String::as_str))
+                .collect(),
+        };
+        let mut row = BatchRow {
+            line: *line,
+            file: file_name(record.get("filename"), *line, &mut used),
+            ..BatchRow::default()
+        };
+        let rendered = row_content(&record).and_then(|(content, text)| {
+            row.summary = content_summary(&content, &text);
+            let options = row_options(&record, &base)?;
+            ui.check_request(&text, options.size)?;
+            render_qr_png_bytes(&text, &options)
+        });
+        match rendered {
+            Ok(bytes) => {
+                zip.start_file(row.file.as_str(), options)
+                    .and_then(|_| Ok(zip.write_all(&bytes)?))
+                    .map_err(|e| format!("Archive ZIP : {}", e))?;
+            }
+            Err(e) => row.error = Some(e),
+        }
+        let fields = [
+            line.to_string(),
+            if row.error.is_none() {
+                row.file.clone()
+            } else {
+                String::new()
+            },
+            row.summary.clone(),
+            if row.error.is_none() { "ok" } else { "erreur" }.to_string(),
+            row.error.clone().unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        manifest.push_str(&fields.join(","));
+        manifest.push_str("\r\n");
+        rows.push(row);
+    }
+    zip.start_file(MANIFEST_NAME, options)
+        .and_then(|_| Ok(zip.write_all(manifest.as_bytes())?))
+        .map_err(|e| format!("Archive ZIP : {}", e))?;
+    let archive = zip
+        .finish()
+        .map_err(|e| format!("Archive ZIP : {}", e))?
+        .into_inner();
+    Ok(Batch { rows, archive })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    fn entries(archive: &[u8]) -> Vec<String> {
+        let zip = ZipArchive::new(Cursor::new(archive)).unwrap();
+        zip.file_names().map(String::from).collect()
+    }
+
+    fn manifest(archive: &[u8]) -> String {
+        let mut zip = ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut manifest = String::new();
+        zip.by_name(MANIFEST_NAME)
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .unwrap();
+        manifest
+    }
+
+    #[test]
+    fn test_render_batch() {
+        let csv = "type,text,filename,ssid,password,first_name,phone,size,foreground\n\
+                   text,https://example.com,site,,,,,512,#336699\n\
+                   wifi,,,Maison,secret,,,,\n\
+                   contact,,carte.png,,,Alice,0102|0304,,\n";
+        let batch = render_batch(csv, &UiSettings::default()).unwrap();
+        assert_eq!(batch.rows.len(), 3);
+        assert!(batch.rows.iter().all(|row| row.error.is_none()));
+        assert_eq!(batch.rows[0].file, "site.png");
+        assert_eq!(batch.rows[1].file, "qr-3.png");
+        assert_eq!(batch.rows[1].summary, "Wi-Fi : Maison");
+        assert_eq!(batch.rows[2].file, "carte.png");
+
+        let mut names = entries(&batch.archive);
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["carte.png", "manifest.csv", "qr-3.png", "site.png"]
+        );
+        let mut zip = ZipArchive::new(Cursor::new(&batch.archive)).unwrap();
+        let mut png = Vec::new();
+        zip.by_name("site.png")
+            .unwrap()
+            .read_to_end(&mut png)
+            .unwrap();
+        let options = RenderOptions {
+            size: 512,
+            foreground: QrColor::try_from("#336699".to_string()).unwrap(),
+            ..UIQr::new(String::new(), &UiSettings::default()).render_options()
+        };
+        assert_eq!(
+            png,
+            render_qr_png_bytes("https://example.com", &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_batch_reports_row_errors() {
+        let csv = "text;size;error_correction\n\
+                   ok;;\n\
+                   ;;\n\
+                   taille;300;\n\
+                   niveau;;Z\n";
+        let batch = render_batch(csv, &UiSettings::default()).unwrap();
+        let errors: Vec<_> = batch.rows.iter().map(|row| row.error.is_some()).collect();
+        assert_eq!(errors, vec![false, true, true]);
+        assert_eq!(batch.rows[1].line, 4);
+        assert!(batch.rows[1].error.as_ref().unwrap().contains("300"));
+        assert!(batch.rows[2]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("error_correction"));
+
+        assert_eq!(entries(&batch.archive).len(), 2);
+        let manifest = manifest(&batch.archive);
+        let lines: Vec<_> = manifest.lines().collect();
+        assert_eq!(lines[0], "line,file,content,status,error");
+        assert_eq!(lines[1], "2,qr-2.png,ok,ok,");
+        assert!(lines[2].starts_with("4,,taille,erreur,"));
+    }
+
+    /// Nombre maximal de lignes accepté, rendues en petite taille pour que
+    /// le test reste rapide sans optimisations.
+    #[test]
+    fn test_render_batch_largest() {
+        let size = 32;
+        let ui = UiSettings {
+            default_size: size,
+            allowed_sizes: vec![size],
+            ..UiSettings::default()
+        };
+        let rows: String = (0..MAX_BATCH_ROWS)
+            .map(|i| format!("https://example.com/{},{}\n", i, size))
+            .collect();
+        let batch = render_batch(&format!("text,size\n{}", rows), &ui).unwrap();
+        assert_eq!(batch.rows.len(), MAX_BATCH_ROWS);
+        assert!(batch.rows.iter().all(|row| row.error.is_none()));
+        assert_eq!(entries(&batch.archive).len(), MAX_BATCH_ROWS + 1);
+    }
+
+    #[test]
+    fn test_render_batch_rejects_file() {
+        let ui = UiSettings::default();
+        assert!(render_batch("", &ui).is_err());
+        assert!(render_batch("filename\nun", &ui).is_err());
+        assert!(render_batch("text,colour\nun,rouge", &ui).is_err());
+        let csv = format!("text\n{}", "abc\n".repeat(MAX_BATCH_ROWS + 1));
+        assert!(render_batch(&csv, &ui).is_err());
+    }
+
+    #[test]
+    fn test_file_name() {
+        let mut used = HashSet::new();
+        assert_eq!(file_name("étiquette 1", 2, &mut used), "étiquette_1.png");
+        assert_eq!(
+            file_name("étiquette 1.png", 3, &mut used),
+            "étiquette_1-2.png"
+        );
+        assert_eq!(
+            file_name("../../etc/passwd", 4, &mut used),
+            "_.._etc_passwd.png"
+        );
+        assert_eq!(file_name("", 5, &mut used), "qr-5.png");
+        assert_eq!(file_name("manifest.csv", 6, &mut used), "manifest.csv.png");
+    }
+}
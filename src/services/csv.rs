@@ -0,0 +1,140 @@
+//! Lecture et écriture de fichiers CSV (RFC 4180)
+//!
+//! Les tableurs français séparent souvent les colonnes par `;` : le
+//! séparateur d'un fichier importé est déduit de sa première ligne.
+
+/// Échappe un champ CSV (RFC 4180) ; les valeurs commençant par un
+/// caractère de formule sont préfixées pour les tableurs.
+pub fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Séparateur le plus fréquent de la première ligne (`,` par défaut).
+fn detect_delimiter(input: &str) -> char {
+    let header = input.lines().next().unwrap_or_default();
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|&delimiter| header.matches(delimiter).count())
+        .filter(|&delimiter| header.contains(delimiter))
+        .unwrap_or(',')
+}
+
+/// Découpe un fichier CSV en lignes de champs ; les lignes vides sont
+/// ignorées et chaque ligne est accompagnée de son numéro (à partir de 1).
+pub fn parse_csv(input: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let delimiter = detect_delimiter(input);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|value| !value.trim().is_empty()) {
+                    rows.push((row_line, std::mem::take(&mut row)));
+                }
+                row.clear();
+                line += 1;
+                row_line = line;
+            }
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("Guillemet non fermé (ligne {})", row_line));
+    }
+    row.push(field);
+    if row.iter().any(|value| !value.trim().is_empty()) {
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(rows: Vec<(usize, Vec<String>)>) -> Vec<(usize, Vec<&'static str>)> {
+        rows.into_iter()
+            .map(|(line, row)| {
+                let row = row
+                    .into_iter()
+                    .map(|value| &*Box::leak(value.into_boxed_str()))
+                    .collect();
+                (line, row)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("simple"), "simple");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("dit \"oui\""), "\"dit \"\"oui\"\"\"");
+        assert_eq!(csv_field("=1+1"), "'=1+1");
+    }
+
+    #[test]
+    fn test_parse_csv_quotes_and_line_endings() {
+        let input =
+            "text,filename\r\n\"a,b\",un.png\r\n\r\n\"sur\ndeux lignes\",\"dit \"\"oui\"\"\"\n";
+        assert_eq!(
+            values(parse_csv(input).unwrap()),
+            vec![
+                (1, vec!["text", "filename"]),
+                (2, vec!["a,b", "un.png"]),
+                (4, vec!["sur\ndeux lignes", "dit \"oui\""]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_detects_semicolon() {
+        let input = "\u{feff}text;size\nhttps://example.com/a,b;512";
+        assert_eq!(
+            values(parse_csv(input).unwrap()),
+            vec![
+                (1, vec!["text", "size"]),
+                (2, vec!["https://example.com/a,b", "512"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_rejects_unclosed_quote() {
+        assert!(parse_csv("text\n\"ouvert").is_err());
+    }
+}
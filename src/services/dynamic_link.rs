@@ -0,0 +1,194 @@
+//! QR codes dynamiques
+//!
+//! Le QR code imprimé encode une URL courte du serveur (`/r/<slug>`) ; la
+//! cible de la redirection est stockée avec le [`SavedQr`] et peut changer
+//! sans modifier l'image. L'unicité du slug est vérifiée par le stockage
+//! lors de la sauvegarde.
+
+use crate::models::qr_code::{DynamicLink, QrId, SavedQr};
+use crate::services::storage::QrStorage;
+
+/// Longueur maximale d'une URL de destination
+pub const MAX_TARGET_LENGTH: usize = 2048;
+
+/// Vérifie qu'une cible de redirection est une URL http(s) absolue.
+pub fn validate_target(target: &str) -> Result<(), String> {
+    let rest = target
+        .strip_prefix("https://")
+        .or_else(|| target.strip_prefix("http://"))
+        .ok_or("L'URL de destination doit commencer par http:// ou https://")?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() {
+        return Err("L'URL de destination n'indique pas de domaine".into());
+    }
+    if target.len() > MAX_TARGET_LENGTH {
+        return Err(format!(
+            "L'URL de destination dépasse {} caractères",
+            MAX_TARGET_LENGTH
+        ));
+    }
+    if target.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(
+            "L'URL de destination contient des espaces ou des caractères de contrôle".into(),
+        );
+    }
+    Ok(())
+}
+
+pub fn validate_link(link: &DynamicLink) -> Result<(), String> {
+    if !DynamicLink::is_valid_slug(&link.slug) {
+        return Err(format!("Lien court invalide : {:?}", link.slug));
+    }
+    validate_target(&link.target)
+}
+
+pub fn slug_in_use(link: &DynamicLink) -> String {
+    format!(
+        "Le lien court {} est déjà utilisé par un autre QR code",
+        link.slug
+    )
+}
+
+/// Vérifie le lien d'un QR code avant sauvegarde : le texte encodé doit être
+/// l'URL courte du slug. Sans `public_url` configurée, l'origine dépend de la
+/// page de l'interface et seul le chemin `/r/<slug>` est vérifié.
+pub fn check_dynamic(qr: &SavedQr, public_url: &str) -> Result<(), String> {
+    let Some(link) = &qr.dynamic else {
+        return Ok(());
+    };
+    validate_link(link)?;
+    let expected = link.short_url(public_url);
+    let matches = if public_url.is_empty() {
+        validate_target(&qr.text).is_ok()
+            && qr
+                .text
+                .split_once("://")
+                .and_then(|(_, rest)| rest.find('/').map(|path| &rest[path..]))
+                == Some(expected.as_str())
+    } else {
+        qr.text == expected
+    };
+    if !matches {
+        return Err(format!(
+            "Le texte d'un QR code dynamique doit être son URL courte ({})",
+            expected
+        ));
+    }
+    Ok(())
+}
+
+/// QR code dynamique utilisant un slug et cible de sa redirection.
+pub fn resolve(storage: &dyn QrStorage, slug: &str) -> Result<Option<(QrId, String)>, String> {
+    if !DynamicLink::is_valid_slug(slug) {
+        return Ok(None);
+    }
+    Ok(storage
+        .find_by_slug(slug)?
+        .and_then(|qr| Some((qr.id, qr.dynamic?.target))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::{JsonDirStorage, SaveError};
+
+    fn dynamic(id: &str, slug: &str, target: &str) -> SavedQr {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": format!("https://qr.example.com/r/{}", slug),
+            "size": 256,
+            "transparent": false,
+            "margin": true,
+            "created_at": "0",
+            "dynamic": { "slug": slug, "target": target },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_generated_link() {
+        let link = DynamicLink::generate("https://example.com".into());
+        assert_eq!(link.slug.len(), DynamicLink::SLUG_LENGTH);
+        assert!(validate_link(&link).is_ok());
+        assert_eq!(
+            link.short_url("https://qr.example.com/"),
+            format!("https://qr.example.com/r/{}", link.slug)
+        );
+    }
+
+    #[test]
+    fn test_validate_target() {
+        assert!(validate_target("https://example.com/promo?x=1").is_ok());
+        assert!(validate_target("http://example.com").is_ok());
+        assert!(validate_target("javascript:alert(1)").is_err());
+        assert!(validate_target("ftp://example.com").is_err());
+        assert!(validate_target("https://").is_err());
+        assert!(validate_target("https:///chemin").is_err());
+        assert!(validate_target("https://example.com/a b").is_err());
+        assert!(validate_target(&format!("https://example.com/{}", "a".repeat(3000))).is_err());
+    }
+
+    #[test]
+    fn test_text_must_be_short_url() {
+        let mut qr = dynamic("qr-1", "promo", "https://example.com");
+        assert!(check_dynamic(&qr, "").is_ok());
+        assert!(check_dynamic(&qr, "https://qr.example.com").is_ok());
+        assert!(check_dynamic(&qr, "https://autre.example.com").is_err());
+        for text in [
+            "https://example.com",
+            "https://qr.example.com/r/autre",
+            "https://qr.example.com/r/promo?x=1",
+            "/r/promo",
+        ] {
+            qr.text = text.into();
+            assert!(check_dynamic(&qr, "").is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_invalid_slugs() {
+        for slug in ["", "../x", "a/b", "é", "a b", &"a".repeat(65)] {
+            assert!(!DynamicLink::is_valid_slug(slug), "{:?}", slug);
+        }
+        assert!(DynamicLink::is_valid_slug("Promo-2024_a"));
+    }
+
+    #[test]
+    fn test_resolve_and_uniqueness() {
+        let dir = std::env::temp_dir().join(format!("qr-craft-test-{}", fastrand::u64(..)));
+        let storage = JsonDirStorage::new(&dir);
+        let qr = dynamic("qr-1", "promo", "https://example.com/ete");
+        check_dynamic(&qr, "").unwrap();
+        storage.save(&qr).unwrap();
+
+        assert_eq!(
+            resolve(&storage, "promo").unwrap(),
+            Some((qr.id.clone(), "https://example.com/ete".into()))
+        );
+        assert_eq!(resolve(&storage, "inconnu").unwrap(), None);
+        assert_eq!(resolve(&storage, "../qr-1").unwrap(), None);
+
+        // La cible change sans toucher au texte encodé
+        let mut edited = storage.load(&qr.id).unwrap();
+        edited.dynamic.as_mut().unwrap().target = "https://example.com/hiver".into();
+        check_dynamic(&edited, "https://qr.example.com/").unwrap();
+        storage.save(&edited).unwrap();
+        assert_eq!(
+            resolve(&storage, "promo")
+                .unwrap()
+                .map(|(_, target)| target),
+            Some("https://example.com/hiver".into())
+        );
+        assert_eq!(storage.load(&qr.id).unwrap().text, qr.text);
+
+        let other = dynamic("qr-2", "promo", "https://example.com");
+        assert_eq!(
+            storage.save(&other),
+            Err(SaveError::Conflict(slug_in_use(
+                other.dynamic.as_ref().unwrap()
+            )))
+        );
+        assert!(check_dynamic(&dynamic("qr-3", "../x", "https://example.com"), "").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
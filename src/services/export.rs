@@ -0,0 +1,126 @@
+//! Export des QR codes vers des fichiers, pour le client en ligne de commande
+//!
+//! Le rendu est celui de l'application ; seules les limites de l'interface
+//! (tailles autorisées, longueur maximale) ne s'appliquent pas.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::models::qr_code::{ExportFormat, QrId, RenderOptions, SavedQr};
+use crate::services::qr_code::{render_qr_png_bytes, render_qr_svg};
+use crate::services::storage::QrStorage;
+
+/// Côté maximal d'une image exportée, en pixels
+pub const MAX_EXPORT_SIZE: u32 = 4096;
+
+/// Rend un QR code dans le format demandé.
+pub fn render_export(
+    text: &str,
+    options: &RenderOptions,
+    format: ExportFormat,
+) -> Result<Vec<u8>, String> {
+    if options.size == 0 || options.size > MAX_EXPORT_SIZE {
+        return Err(format!(
+            "Taille invalide : {}px (de 1 à {}px)",
+            options.size, MAX_EXPORT_SIZE
+        ));
+    }
+    match format {
+        ExportFormat::Png => render_qr_png_bytes(text, options),
+        ExportFormat::Svg => render_qr_svg(text, options).map(String::into_bytes),
+    }
+}
+
+/// Nom du fichier exporté pour un QR code sauvegardé
+pub fn export_file_name(id: &QrId, format: ExportFormat) -> String {
+    format!("{}.{}", id, format.extension())
+}
+
+/// QR codes sauvegardés à exporter : ceux demandés, ou tous les QR codes
+/// lisibles si `ids` est vide.
+pub fn select_saved(storage: &dyn QrStorage, ids: &[QrId]) -> Result<Vec<SavedQr>, String> {
+    if ids.is_empty() {
+        return Ok(storage.list()?.codes);
+    }
+    ids.iter().map(|id| storage.load(id)).collect()
+}
+
+/// Écrit chaque QR code dans `dir` (créé au besoin) et renvoie les fichiers
+/// produits.
+pub fn export_saved(
+    codes: &[SavedQr],
+    dir: &Path,
+    format: ExportFormat,
+) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Création de {:?} impossible : {}", dir, e))?;
+    codes
+        .iter()
+        .map(|qr| {
+            let bytes = render_export(&qr.text, &qr.render_options(), format)
+                .map_err(|e| format!("QR code {} : {}", qr.id, e))?;
+            let path = dir.join(export_file_name(&qr.id, format));
+            fs::write(&path, bytes)
+                .map_err(|e| format!("Écriture de {:?} impossible : {}", path, e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::JsonDirStorage;
+
+    fn saved(id: &str, text: &str) -> SavedQr {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": text,
+            "size": 128,
+            "transparent": false,
+            "margin": true,
+            "created_at": "100",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_export_formats() {
+        let options = RenderOptions::default();
+        let png = render_export("https://example.com", &options, ExportFormat::Png).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let svg = render_export("https://example.com", &options, ExportFormat::Svg).unwrap();
+        assert!(String::from_utf8(svg).unwrap().contains("<svg"));
+    }
+
+    #[test]
+    fn test_render_export_rejects_size() {
+        for size in [0, MAX_EXPORT_SIZE + 1] {
+            let options = RenderOptions {
+                size,
+                ..Default::default()
+            };
+            assert!(render_export("abc", &options, ExportFormat::Png).is_err());
+        }
+    }
+
+    #[test]
+    fn test_export_saved() {
+        let dir = std::env::temp_dir().join(format!("qr-craft-test-{}", fastrand::u64(..)));
+        let storage = JsonDirStorage::new(dir.join("data"));
+        storage.save(&saved("qr-1", "premier")).unwrap();
+        storage.save(&saved("qr-2", "second")).unwrap();
+
+        let all = select_saved(&storage, &[]).unwrap();
+        assert_eq!(all.len(), 2);
+        let one = select_saved(&storage, &[QrId::parse("qr-2").unwrap()]).unwrap();
+        assert_eq!(one[0].text, "second");
+        assert!(select_saved(&storage, &[QrId::parse("qr-3").unwrap()]).is_err());
+
+        let out = dir.join("export");
+        let files = export_saved(&all, &out, ExportFormat::Svg).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(out.join("qr-1.svg").exists());
+        assert!(out.join("qr-2.svg").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+//! Images PNG servies en HTTP simple
+//!
+//! `GET /api/qr.png?text=...&size=...&margin=...` et `GET /api/saved/<id>.png`
+//! permettent d'intégrer un QR code avec une simple balise `<img>`. Les
+//! réponses portent un ETag dérivé des paramètres de rendu.
+
+use serde::Deserialize;
+
+use crate::config::UiSettings;
+use crate::models::qr_code::{MarginEnabled, QrId, RenderOptions, UIQr};
+use crate::services::qr_code::render_fingerprint;
+
+/// Cache des images ad hoc : l'URL détermine l'image, mais son rendu peut
+/// changer avec une nouvelle version ; revalidée via l'ETag après une heure.
+pub const QUERY_CACHE_CONTROL: &str = "public, max-age=3600, must-revalidate";
+
+/// Cache des QR codes sauvegardés : modifiables, donc revalidés via l'ETag.
+pub const SAVED_CACHE_CONTROL: &str = "no-cache";
+
+/// Paramètres de `/api/qr.png`
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ImageQuery {
+    pub text: String,
+    pub size: Option<u32>,
+    pub margin: Option<bool>,
+}
+
+impl ImageQuery {
+    /// Options de rendu de la requête, les valeurs absentes prenant les
+    /// défauts configurés.
+    pub fn render_options(&self, ui: &UiSettings) -> Result<RenderOptions, String> {
+        if self.text.is_empty() {
+            return Err("Le paramètre text est requis".into());
+        }
+        let size = self.size.unwrap_or(ui.default_size);
+        ui.check_request(&self.text, size)?;
+        let options = UIQr::new(self.text.clone(), ui).render_options();
+        Ok(RenderOptions {
+            size,
+            margin: MarginEnabled(self.margin.unwrap_or(ui.default_margin)),
+            ..options
+        })
+    }
+}
+
+/// Identifiant d'un QR code sauvegardé à partir du nom `<id>.png`.
+pub fn saved_id(file: &str) -> Result<QrId, String> {
+    let id = file
+        .strip_suffix(".png")
+        .ok_or_else(|| format!("Image inconnue : {:?}", file))?;
+    QrId::parse(id)
+}
+
+/// ETag fort d'un rendu.
+pub fn etag(text: &str, options: &RenderOptions) -> String {
+    format!("\"{:016x}\"", render_fingerprint(text, options))
+}
+
+/// Indique si l'en-tête `If-None-Match` désigne l'ETag courant.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(text: &str, size: Option<u32>, margin: Option<bool>) -> ImageQuery {
+        ImageQuery {
+            text: text.into(),
+            size,
+            margin,
+        }
+    }
+
+    #[test]
+    fn test_query_defaults_and_overrides() {
+        let ui = UiSettings::default();
+        let options = query("https://example.com", None, None)
+            .render_options(&ui)
+            .unwrap();
+        assert_eq!(options.size, ui.default_size);
+        assert_eq!(options.margin, MarginEnabled(ui.default_margin));
+
+        let options = query("https://example.com", Some(512), Some(false))
+            .render_options(&ui)
+            .unwrap();
+        assert_eq!(options.size, 512);
+        assert_eq!(options.margin, MarginEnabled(false));
+    }
+
+    #[test]
+    fn test_query_is_checked_against_settings() {
+        let ui = UiSettings::default();
+        assert!(query("", None, None).render_options(&ui).is_err());
+        assert!(query("x", Some(300), None).render_options(&ui).is_err());
+        let long = "a".repeat(ui.max_payload_length + 1);
+        assert!(query(&long, None, None).render_options(&ui).is_err());
+    }
+
+    #[test]
+    fn test_saved_id() {
+        assert_eq!(saved_id("qr-42.png").unwrap().as_str(), "qr-42");
+        assert!(saved_id("qr-42").is_err());
+        assert!(saved_id("qr-42.svg").is_err());
+        assert!(saved_id("..%2Fsecret.png").is_err());
+        assert!(saved_id("../qr-1.png").is_err());
+    }
+
+    #[test]
+    fn test_etag() {
+        let ui = UiSettings::default();
+        let options = query("a", None, None).render_options(&ui).unwrap();
+        let tag = etag("a", &options);
+        assert!(tag.starts_with('"') && tag.ends_with('"'));
+        assert_eq!(tag, etag("a", &options));
+        assert_ne!(tag, etag("b", &options));
+        let bigger = RenderOptions {
+            size: 512,
+            ..options.clone()
+        };
+        assert_ne!(tag, etag("a", &bigger));
+
+        assert!(etag_matches(&tag, &tag));
+        assert!(etag_matches(&format!("\"autre\", W/{}", tag), &tag));
+        assert!(etag_matches("*", &tag));
+        assert!(!etag_matches("\"autre\"", &tag));
+    }
+}
@@ -0,0 +1,578 @@
+//! Planches d'étiquettes au format PDF
+//!
+//! Les QR codes sauvegardés sont disposés sur une grille (format de page,
+//! lignes, colonnes, marges) avec une légende sous chacun. Le PDF est écrit
+//! directement : chaque QR code est une image rendue comme l'export PNG,
+//! dont les données compressées sont reprises telles quelles (filtre
+//! `FlateDecode` avec prédicteurs PNG), et les légendes utilisent la police
+//! standard Helvetica, disponible dans tous les lecteurs PDF.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+use image::ImageEncoder;
+
+use crate::models::labels::{LabelCaption, LabelSheet};
+use crate::models::qr_code::{RenderOptions, SavedQr};
+use crate::services::export::MAX_EXPORT_SIZE;
+use crate::services::payload::content_summary;
+use crate::services::qr_code::render_qr_image;
+
+/// Nombre maximal de lignes ou de colonnes d'une planche
+pub const MAX_GRID: u32 = 20;
+
+/// Nombre maximal d'exemplaires de chaque QR code
+pub const MAX_COPIES: u32 = 100;
+
+/// Nombre maximal de QR codes par document
+pub const MAX_LABEL_CODES: usize = 500;
+
+/// Côté minimal d'un QR code imprimé, en millimètres
+pub const MIN_QR_MM: f64 = 10.0;
+
+/// Résolution des images, en pixels par pouce
+const IMAGE_DPI: f64 = 300.0;
+
+/// Chasse des caractères ASCII imprimables (32 à 126) de Helvetica, en
+/// millièmes de la taille de police
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Millimètres en points PDF (1/72 de pouce)
+fn mm(value: f64) -> f64 {
+    value * 72.0 / 25.4
+}
+
+/// Dimensions d'une planche, en points
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Grid {
+    page_width: f64,
+    page_height: f64,
+    margin: f64,
+    spacing: f64,
+    cell_width: f64,
+    cell_height: f64,
+    /// Taille de police des légendes (0 sans légende)
+    font_size: f64,
+    /// Côté des QR codes
+    side: f64,
+}
+
+impl Grid {
+    fn new(sheet: &LabelSheet) -> Result<Self, String> {
+        for (name, value) in [("colonnes", sheet.columns), ("lignes", sheet.rows)] {
+            if value == 0 || value > MAX_GRID {
+                return Err(format!(
+                    "Nombre de {} invalide : {} (de 1 à {})",
+                    name, value, MAX_GRID
+                ));
+            }
+        }
+        if sheet.copies == 0 || sheet.copies > MAX_COPIES {
+            return Err(format!(
+                "Nombre d'exemplaires invalide : {} (de 1 à {})",
+                sheet.copies, MAX_COPIES
+            ));
+        }
+        for (name, value) in [("Marge", sheet.margin_mm), ("Espacement", sheet.spacing_mm)] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{} invalide : {} mm", name, value));
+            }
+        }
+        let (page_width, page_height) = sheet.page.dimensions_mm();
+        let (page_width, page_height) = (mm(page_width), mm(page_height));
+        let (margin, spacing) = (mm(sheet.margin_mm), mm(sheet.spacing_mm));
+        let cell = |length: f64, count: u32| {
+            (length - 2.0 * margin - (count - 1) as f64 * spacing) / count as f64
+        };
+        let cell_width = cell(page_width, sheet.columns);
+        let cell_height = cell(page_height, sheet.rows);
+        let font_size = match sheet.caption {
+            LabelCaption::None => 0.0,
+            _ => (cell_height * 0.08).clamp(5.0, 9.0),
+        };
+        let side = cell_width.min(cell_height - 1.5 * font_size);
+        if side < mm(MIN_QR_MM) {
+            return Err(format!(
+                "Étiquettes trop petites : les QR codes doivent mesurer au moins {} mm",
+                MIN_QR_MM
+            ));
+        }
+        Ok(Grid {
+            page_width,
+            page_height,
+            margin,
+            spacing,
+            cell_width,
+            cell_height,
+            font_size,
+            side,
+        })
+    }
+}
+
+/// Nombre PDF compact (deux décimales au plus)
+fn number(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Octet WinAnsi (Windows-1252) d'un caractère, `?` s'il n'y figure pas
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        'Œ' => 0x8c,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        'œ' => 0x9c,
+        'Ÿ' => 0x9f,
+        _ => b'?',
+    }
+}
+
+/// Chasse approximative d'un caractère en Helvetica, en millièmes
+fn char_width(c: char) -> f64 {
+    match c {
+        ' '..='~' => HELVETICA_WIDTHS[c as usize - 32] as f64,
+        '…' => 1000.0,
+        _ => 556.0,
+    }
+}
+
+fn text_width(text: &str, font_size: f64) -> f64 {
+    text.chars().map(char_width).sum::<f64>() * font_size / 1000.0
+}
+
+/// Légende tenant sur `max_width` points, tronquée par des points de suspension
+fn fit_caption(text: &str, max_width: f64, font_size: f64) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let text = text.trim();
+    if text_width(text, font_size) <= max_width {
+        return text.to_string();
+    }
+    let mut width = text_width("…", font_size);
+    let mut fitted = String::new();
+    for c in text.chars() {
+        width += char_width(c) * font_size / 1000.0;
+        if width > max_width {
+            break;
+        }
+        fitted.push(c);
+    }
+    format!("{}…", fitted.trim_end())
+}
+
+/// Chaîne littérale PDF en codage WinAnsi
+fn pdf_string(text: &str) -> String {
+    let mut literal = String::from("(");
+    for byte in text.chars().map(win_ansi) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            32..=126 => literal.push(byte as char),
+            _ => {
+                let _ = write!(literal, "\\{:03o}", byte);
+            }
+        }
+    }
+    literal.push(')');
+    literal
+}
+
+/// Données compressées (chunks `IDAT`) d'un fichier PNG
+fn png_idat(png: &[u8]) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let mut rest = png.get(8..).unwrap_or_default();
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let chunk = rest.get(8..8 + length).ok_or("Image PNG tronquée")?;
+        if &rest[4..8] == b"IDAT" {
+            data.extend_from_slice(chunk);
+        }
+        rest = rest.get(12 + length..).unwrap_or_default();
+    }
+    if data.is_empty() {
+        return Err("Image PNG sans données".into());
+    }
+    Ok(data)
+}
+
+/// Image d'un QR code pour un côté de `side` points : largeur, hauteur et
+/// données RGB compressées (fond transparent aplati sur du blanc)
+fn label_image(qr: &SavedQr, side: f64) -> Result<(u32, u32, Vec<u8>), String> {
+    let size = ((side / 72.0 * IMAGE_DPI).ceil() as u32).clamp(64, MAX_EXPORT_SIZE);
+    let options = RenderOptions {
+        size,
+        ..qr.render_options()
+    };
+    let image = render_qr_image(&qr.text, &options)?;
+    let rgb = image::RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let over_white = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([over_white(r), over_white(g), over_white(b)])
+    });
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(
+            rgb.as_raw(),
+            rgb.width(),
+            rgb.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok((rgb.width(), rgb.height(), png_idat(&png)?))
+}
+
+/// Écriture séquentielle des objets d'un document PDF
+struct PdfWriter {
+    buffer: Vec<u8>,
+    /// Position de chaque objet (numérotés à partir de 1)
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        PdfWriter {
+            buffer: b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Réserve un numéro d'objet, écrit plus tard
+    fn reserve(&mut self) -> usize {
+        self.offsets.push(0);
+        self.offsets.len()
+    }
+
+    fn object(&mut self, id: usize, body: &str) {
+        self.offsets[id - 1] = self.buffer.len();
+        self.buffer
+            .extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", id, body).as_bytes());
+    }
+
+    fn stream(&mut self, id: usize, dictionary: &str, data: &[u8]) {
+        self.offsets[id - 1] = self.buffer.len();
+        self.buffer.extend_from_slice(
+            format!(
+                "{} 0 obj\n<< {}/Length {} >>\nstream\n",
+                id,
+                dictionary,
+                data.len()
+            )
+            .as_bytes(),
+        );
+        self.buffer.extend_from_slice(data);
+        self.buffer.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    fn finish(mut self, root: usize) -> Vec<u8> {
+        let xref = self.buffer.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            root,
+            xref
+        );
+        self.buffer.extend_from_slice(table.as_bytes());
+        self.buffer
+    }
+}
+
+/// Légende d'un QR code selon la mise en page
+fn caption_text(qr: &SavedQr, caption: LabelCaption) -> String {
+    match caption {
+        LabelCaption::None => String::new(),
+        LabelCaption::Content => content_summary(&qr.content, &qr.text),
+        LabelCaption::Id => qr.id.to_string(),
+    }
+}
+
+/// Dispose les QR codes (chacun répété `sheet.copies` fois) sur autant de
+/// pages que nécessaire et renvoie le document PDF.
+pub fn render_labels(codes: &[SavedQr], sheet: &LabelSheet) -> Result<Vec<u8>, String> {
+    if codes.is_empty() {
+        return Err("Aucun QR code à imprimer".into());
+    }
+    if codes.len() > MAX_LABEL_CODES {
+        return Err(format!(
+            "Trop de QR codes : {} (au plus {})",
+            codes.len(),
+            MAX_LABEL_CODES
+        ));
+    }
+    let grid = Grid::new(sheet)?;
+    let mut pdf = PdfWriter::new();
+    let catalog = pdf.reserve();
+    let pages = pdf.reserve();
+    let font = pdf.reserve();
+    pdf.object(
+        font,
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>",
+    );
+
+    let mut images = Vec::with_capacity(codes.len());
+    for qr in codes {
+        let (width, height, data) =
+            label_image(qr, grid.side).map_err(|e| format!("QR code {} : {}", qr.id, e))?;
+        let image = pdf.reserve();
+        pdf.stream(
+            image,
+            &format!(
+                concat!(
+                    "/Type /XObject /Subtype /Image /Width {w} /Height {h} ",
+                    "/ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode ",
+                    "/DecodeParms << /Predictor 15 /Colors 3 /BitsPerComponent 8 /Columns {w} >> "
+                ),
+                w = width,
+                h = height
+            ),
+            &data,
+        );
+        images.push(image);
+    }
+
+    let labels: Vec<usize> = (0..codes.len())
+        .flat_map(|index| std::iter::repeat_n(index, sheet.copies as usize))
+        .collect();
+    let per_page = (sheet.columns * sheet.rows) as usize;
+    let mut kids = Vec::new();
+    for page_labels in labels.chunks(per_page) {
+        let mut content = String::new();
+        let mut used = BTreeSet::new();
+        for (slot, &index) in page_labels.iter().enumerate() {
+            let column = (slot % sheet.columns as usize) as f64;
+            let row = (slot / sheet.columns as usize) as f64;
+            let left = grid.margin + column * (grid.cell_width + grid.spacing);
+            let top = grid.page_height - grid.margin - row * (grid.cell_height + grid.spacing);
+            // QR code et légende centrés dans l'étiquette
+            let block = grid.side + 1.5 * grid.font_size;
+            let x = left + (grid.cell_width - grid.side) / 2.0;
+            let y = top - (grid.cell_height - block) / 2.0 - grid.side;
+            let _ = writeln!(
+                content,
+                "q {side} 0 0 {side} {x} {y} cm /Im{index} Do Q",
+                side = number(grid.side),
+                x = number(x),
+                y = number(y),
+            );
+            used.insert(index);
+
+            let caption = fit_caption(
+                &caption_text(&codes[index], sheet.caption),
+                grid.cell_width,
+                grid.font_size,
+            );
+            if !caption.is_empty() {
+                let width = text_width(&caption, grid.font_size);
+                let _ = writeln!(
+                    content,
+                    "BT /F1 {size} Tf {x} {y} Td {text} Tj ET",
+                    size = number(grid.font_size),
+                    x = number(left + (grid.cell_width - width) / 2.0),
+                    y = number(y - 1.1 * grid.font_size),
+                    text = pdf_string(&caption),
+                );
+            }
+        }
+        let contents = pdf.reserve();
+        pdf.stream(contents, "", content.as_bytes());
+        let xobjects: String = used
+            .iter()
+            .map(|&index| format!("/Im{} {} 0 R ", index, images[index]))
+            .collect();
+        let page = pdf.reserve();
+        pdf.object(
+            page,
+            &format!(
+                concat!(
+                    "<< /Type /Page /Parent {pages} 0 R /MediaBox [0 0 {w} {h}] ",
+                    "/Resources << /Font << /F1 {font} 0 R >> /XObject << {xobjects}>> >> ",
+                    "/Contents {contents} 0 R >>"
+                ),
+                pages = pages,
+                w = number(grid.page_width),
+                h = number(grid.page_height),
+                font = font,
+                xobjects = xobjects,
+                contents = contents,
+            ),
+        );
+        kids.push(page);
+    }
+
+    let kids: Vec<String> = kids.iter().map(|page| format!("{} 0 R", page)).collect();
+    pdf.object(
+        pages,
+        &format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        ),
+    );
+    pdf.object(
+        catalog,
+        &format!("<< /Type /Catalog /Pages {} 0 R >>", pages),
+    );
+    Ok(pdf.finish(catalog))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::labels::PageSize;
+
+    fn saved(id: &str, text: &str) -> SavedQr {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": text,
+            "size": 128,
+            "transparent": true,
+            "margin": true,
+            "created_at": "100",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::new(&LabelSheet::default()).unwrap();
+        assert!((grid.page_width - 595.28).abs() < 0.01);
+        assert!(grid.side <= grid.cell_width);
+        assert!(grid.side + 1.5 * grid.font_size <= grid.cell_height + 1e-9);
+
+        let letter = Grid::new(&LabelSheet {
+            page: PageSize::Letter,
+            caption: LabelCaption::None,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!((letter.page_width - 612.0).abs() < 0.01);
+        assert_eq!(letter.font_size, 0.0);
+
+        for sheet in [
+            LabelSheet {
+                columns: 0,
+                ..Default::default()
+            },
+            LabelSheet {
+                rows: MAX_GRID + 1,
+                ..Default::default()
+            },
+            LabelSheet {
+                copies: 0,
+                ..Default::default()
+            },
+            LabelSheet {
+                margin_mm: -1.0,
+                ..Default::default()
+            },
+            LabelSheet {
+                margin_mm: 100.0,
+                ..Default::default()
+            },
+            LabelSheet {
+                columns: 20,
+                rows: 20,
+                ..Default::default()
+            },
+        ] {
+            assert!(Grid::new(&sheet).is_err(), "{:?}", sheet);
+        }
+    }
+
+    #[test]
+    fn test_pdf_string() {
+        assert_eq!(pdf_string("abc"), "(abc)");
+        assert_eq!(pdf_string("(a\\b)"), "(\\(a\\\\b\\))");
+        assert_eq!(pdf_string("é€✓"), "(\\351\\200?)");
+    }
+
+    #[test]
+    fn test_fit_caption() {
+        assert_eq!(fit_caption("Bureau 12", 100.0, 8.0), "Bureau 12");
+        assert_eq!(fit_caption("ligne\nsuivante", 100.0, 8.0), "ligne suivante");
+        let long = "https://example.com/un/chemin/beaucoup/trop/long";
+        let fitted = fit_caption(long, 60.0, 8.0);
+        assert!(fitted.ends_with('…'));
+        assert!(text_width(&fitted, 8.0) <= 60.0);
+    }
+
+    #[test]
+    fn test_png_idat() {
+        let (width, height, data) = label_image(&saved("qr-1", "abc"), 72.0).unwrap();
+        assert_eq!(width, height);
+        assert!(width >= 300);
+        // En-tête zlib
+        assert_eq!(data[0] & 0x0f, 8);
+        assert!(png_idat(b"\x89PNG\r\n\x1a\n").is_err());
+    }
+
+    #[test]
+    fn test_render_labels() {
+        let codes = [saved("qr-1", "premier"), saved("qr-2", "second")];
+        let sheet = LabelSheet {
+            columns: 1,
+            rows: 3,
+            copies: 2,
+            ..Default::default()
+        };
+        let pdf = render_labels(&codes, &sheet).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 2"));
+        assert_eq!(text.matches("/Subtype /Image").count(), 2);
+        assert_eq!(text.matches("(premier) Tj").count(), 2);
+        assert_eq!(text.matches("(second) Tj").count(), 2);
+
+        // Table des références croisées
+        let start: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        let table = std::str::from_utf8(&pdf[start..]).unwrap();
+        assert!(table.starts_with("xref\n"));
+        let entries: Vec<&str> = table
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with("n "))
+            .collect();
+        assert!(!entries.is_empty());
+        for (n, entry) in entries.iter().enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", n + 1).as_bytes()));
+        }
+
+        assert!(render_labels(&[], &sheet).is_err());
+    }
+}
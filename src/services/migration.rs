@@ -0,0 +1,120 @@
+//! Migration des QR codes sauvegardés
+//!
+//! Chaque enregistrement porte un `schema_version` (absent = 0). À la lecture,
+//! les étapes de [`MIGRATIONS`] sont appliquées dans l'ordre jusqu'à
+//! [`SCHEMA_VERSION`], sur le JSON brut, avant la désérialisation.
+
+use serde_json::{Map, Value};
+
+use crate::models::qr_code::{SavedQr, SCHEMA_VERSION};
+
+/// Étape de migration : transforme un enregistrement de la version `n` vers `n + 1`.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// `MIGRATIONS[n]` migre de la version `n` vers la version `n + 1`.
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [drop_image_data];
+
+/// v0 → v1 : l'image PNG complète n'est plus stockée (rendue à la demande).
+fn drop_image_data(record: &mut Map<String, Value>) -> Result<(), String> {
+    record.remove("image_data");
+    Ok(())
+}
+
+fn schema_version(record: &Map<String, Value>) -> Result<u32, String> {
+    match record.get("schema_version") {
+        None => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("Version de schéma invalide : {}", value)),
+    }
+}
+
+/// Met un enregistrement au format courant ; indique s'il a été modifié et
+/// doit être réécrit.
+pub fn migrate(value: Value) -> Result<(Value, bool), String> {
+    let Value::Object(mut record) = value else {
+        return Err("L'enregistrement n'est pas un objet JSON".into());
+    };
+    let version = schema_version(&record)?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Version de schéma {} plus récente que celle prise en charge ({})",
+            version, SCHEMA_VERSION
+        ));
+    }
+    for step in &MIGRATIONS[version as usize..] {
+        step(&mut record)?;
+    }
+    let migrated = version < SCHEMA_VERSION;
+    record.insert("schema_version".into(), SCHEMA_VERSION.into());
+    Ok((Value::Object(record), migrated))
+}
+
+/// Lit un enregistrement sérialisé en appliquant les migrations nécessaires.
+pub fn parse_record(s: &str) -> Result<(SavedQr, bool), String> {
+    let value: Value = serde_json::from_str(s).map_err(|e| e.to_string())?;
+    let (value, migrated) = migrate(value)?;
+    let qr = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    Ok((qr, migrated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn v0() -> Value {
+        json!({
+            "id": "qr-1",
+            "text": "ancien",
+            "size": 256,
+            "transparent": false,
+            "margin": true,
+            "created_at": "0",
+            "image_data": "iVBORw0KGgo="
+        })
+    }
+
+    #[test]
+    fn test_migrate_unversioned_record() {
+        let (value, migrated) = migrate(v0()).unwrap();
+        assert!(migrated);
+        assert_eq!(value["schema_version"], json!(SCHEMA_VERSION));
+        assert!(value.get("image_data").is_none());
+    }
+
+    #[test]
+    fn test_current_record_is_untouched() {
+        let (current, _) = migrate(v0()).unwrap();
+        let (again, migrated) = migrate(current.clone()).unwrap();
+        assert!(!migrated);
+        assert_eq!(again, current);
+    }
+
+    #[test]
+    fn test_serialized_record_is_current() {
+        let (qr, _) = parse_record(&v0().to_string()).unwrap();
+        assert_eq!(qr.schema_version, SCHEMA_VERSION);
+        let (_, migrated) = parse_record(&serde_json::to_string(&qr).unwrap()).unwrap();
+        assert!(!migrated);
+    }
+
+    #[test]
+    fn test_unsupported_records_are_errors() {
+        let mut future = v0();
+        future["schema_version"] = json!(SCHEMA_VERSION + 1);
+        assert!(migrate(future).unwrap_err().contains("plus récente"));
+
+        let mut invalid = v0();
+        invalid["schema_version"] = json!("un");
+        assert!(migrate(invalid).is_err());
+
+        assert!(migrate(json!(["qr-1"])).is_err());
+        assert!(parse_record("{").is_err());
+
+        let mut missing_text = v0();
+        missing_text.as_object_mut().unwrap().remove("text");
+        assert!(parse_record(&missing_text.to_string()).is_err());
+    }
+}
@@ -1 +1,15 @@
+pub mod analytics;
+pub mod batch;
+pub mod csv;
+pub mod dynamic_link;
+pub mod export;
+#[cfg(feature = "server")]
+pub mod image_api;
+pub mod labels;
+pub mod migration;
+pub mod payload;
 pub mod qr_code;
+#[cfg(feature = "server")]
+pub mod rest_api;
+pub mod storage;
+pub mod thumbnail;
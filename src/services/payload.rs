@@ -0,0 +1,285 @@
+//! Construction des charges utiles structurées (Wi-Fi, contact, …)
+
+use crate::models::payload::{ContactFormat, ContactPayload, QrContent, WifiPayload, WifiSecurity};
+
+/// Échappe les caractères réservés de la syntaxe `WIFI:` / `MECARD:`.
+pub fn escape_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Construit la charge utile `WIFI:T:…;S:…;P:…;H:…;;` d'un réseau.
+pub fn wifi_payload(wifi: &WifiPayload) -> Result<String, String> {
+    if wifi.ssid.is_empty() {
+        return Err("Le nom du réseau (SSID) est obligatoire.".into());
+    }
+    if wifi.security != WifiSecurity::None && wifi.password.is_empty() {
+        return Err("Le mot de passe est obligatoire pour un réseau sécurisé.".into());
+    }
+
+    let mut payload = format!(
+        "WIFI:T:{};S:{};",
+        wifi.security.code(),
+        escape_field(&wifi.ssid)
+    );
+    if wifi.security != WifiSecurity::None {
+        payload.push_str(&format!("P:{};", escape_field(&wifi.password)));
+    }
+    if wifi.hidden {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+    Ok(payload)
+}
+
+/// Échappe une valeur de propriété vCard 3.0 (RFC 2426).
+fn escape_vcard(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Valeurs non vides d'une liste saisie par l'utilisateur.
+fn non_empty(values: &[String]) -> impl Iterator<Item = &str> {
+    values.iter().map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+/// Construit une fiche vCard 3.0.
+pub fn vcard_payload(contact: &ContactPayload) -> String {
+    let first = contact.first_name.trim();
+    let last = contact.last_name.trim();
+    let full_name = [first, last]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut lines = vec!["BEGIN:VCARD".to_string(), "VERSION:3.0".to_string()];
+    lines.push(format!(
+        "N:{};{};;;",
+        escape_vcard(last),
+        escape_vcard(first)
+    ));
+    lines.push(format!(
+        "FN:{}",
+        escape_vcard(if full_name.is_empty() {
+            contact.organisation.trim()
+        } else {
+            &full_name
+        })
+    ));
+    let optional = [
+        ("ORG", contact.organisation.trim()),
+        ("TITLE", contact.title.trim()),
+    ];
+    for (key, value) in optional {
+        if !value.is_empty() {
+            lines.push(format!("{}:{}", key, escape_vcard(value)));
+        }
+    }
+    for phone in non_empty(&contact.phones) {
+        lines.push(format!("TEL:{}", escape_vcard(phone)));
+    }
+    for email in non_empty(&contact.emails) {
+        lines.push(format!("EMAIL:{}", escape_vcard(email)));
+    }
+    if !contact.url.trim().is_empty() {
+        lines.push(format!("URL:{}", escape_vcard(contact.url.trim())));
+    }
+    if !contact.address.trim().is_empty() {
+        lines.push(format!(
+            "ADR:;;{};;;;",
+            escape_vcard(contact.address.trim())
+        ));
+    }
+    if !contact.note.trim().is_empty() {
+        lines.push(format!("NOTE:{}", escape_vcard(contact.note.trim())));
+    }
+    lines.push("END:VCARD".to_string());
+    lines.join("\r\n")
+}
+
+/// Construit une fiche MeCard (le titre n'existe pas dans ce format).
+pub fn mecard_payload(contact: &ContactPayload) -> String {
+    let mut payload = String::from("MECARD:");
+    let name = match (contact.last_name.trim(), contact.first_name.trim()) {
+        ("", first) => escape_field(first),
+        (last, "") => escape_field(last),
+        (last, first) => format!("{},{}", escape_field(last), escape_field(first)),
+    };
+    if !name.is_empty() {
+        payload.push_str(&format!("N:{};", name));
+    }
+    if !contact.organisation.trim().is_empty() {
+        payload.push_str(&format!(
+            "ORG:{};",
+            escape_field(contact.organisation.trim())
+        ));
+    }
+    for phone in non_empty(&contact.phones) {
+        payload.push_str(&format!("TEL:{};", escape_field(phone)));
+    }
+    for email in non_empty(&contact.emails) {
+        payload.push_str(&format!("EMAIL:{};", escape_field(email)));
+    }
+    let optional = [
+        ("URL", contact.url.trim()),
+        ("ADR", contact.address.trim()),
+        ("NOTE", contact.note.trim()),
+    ];
+    for (key, value) in optional {
+        if !value.is_empty() {
+            payload.push_str(&format!("{}:{};", key, escape_field(value)));
+        }
+    }
+    payload.push(';');
+    payload
+}
+
+/// Sérialise une fiche contact dans le format choisi.
+pub fn contact_payload(contact: &ContactPayload) -> Result<String, String> {
+    if contact.first_name.trim().is_empty()
+        && contact.last_name.trim().is_empty()
+        && contact.organisation.trim().is_empty()
+    {
+        return Err("Un nom ou une organisation est obligatoire.".into());
+    }
+    Ok(match contact.format {
+        ContactFormat::VCard => vcard_payload(contact),
+        ContactFormat::MeCard => mecard_payload(contact),
+    })
+}
+
+/// Texte à encoder pour un contenu structuré ; `None` pour du texte libre.
+pub fn encode_content(content: &QrContent) -> Option<Result<String, String>> {
+    match content {
+        QrContent::Text => None,
+        QrContent::Wifi(wifi) => Some(wifi_payload(wifi)),
+        QrContent::Contact(contact) => Some(contact_payload(contact)),
+    }
+}
+
+/// Libellé court affiché dans la liste des QR codes sauvegardés.
+pub fn content_summary(content: &QrContent, text: &str) -> String {
+    match content {
+        QrContent::Text => text.to_string(),
+        QrContent::Wifi(wifi) => format!("Wi-Fi : {}", wifi.ssid),
+        QrContent::Contact(contact) => {
+            let name = format!("{} {}", contact.first_name.trim(), contact.last_name.trim());
+            match name.trim() {
+                "" => format!("Contact : {}", contact.organisation.trim()),
+                name => format!("Contact : {}", name),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wifi(ssid: &str, security: WifiSecurity, password: &str, hidden: bool) -> WifiPayload {
+        WifiPayload {
+            ssid: ssid.into(),
+            security,
+            password: password.into(),
+            hidden,
+        }
+    }
+
+    #[test]
+    fn test_wifi_payload_wpa() {
+        let payload = wifi_payload(&wifi("Bureau", WifiSecurity::Wpa, "secret", false)).unwrap();
+        assert_eq!(payload, "WIFI:T:WPA;S:Bureau;P:secret;;");
+    }
+
+    #[test]
+    fn test_wifi_payload_open_hidden() {
+        let payload = wifi_payload(&wifi("Invités", WifiSecurity::None, "ignored", true)).unwrap();
+        assert_eq!(payload, "WIFI:T:nopass;S:Invités;H:true;;");
+    }
+
+    #[test]
+    fn test_wifi_payload_escapes_special_characters() {
+        let payload =
+            wifi_payload(&wifi(r"a;b,c:d\e", WifiSecurity::Wep, r#"p"w;d"#, false)).unwrap();
+        assert_eq!(payload, r#"WIFI:T:WEP;S:a\;b\,c\:d\\e;P:p\"w\;d;;"#);
+    }
+
+    #[test]
+    fn test_wifi_payload_requires_ssid_and_password() {
+        assert!(wifi_payload(&wifi("", WifiSecurity::None, "", false)).is_err());
+        assert!(wifi_payload(&wifi("Bureau", WifiSecurity::Wpa, "", false)).is_err());
+    }
+
+    fn contact(format: ContactFormat) -> ContactPayload {
+        ContactPayload {
+            first_name: "Jeanne".into(),
+            last_name: "Dupont".into(),
+            organisation: "ACME; Inc".into(),
+            title: "Directrice".into(),
+            phones: vec!["+33 1 23 45 67 89".into(), " ".into()],
+            emails: vec!["jeanne@example.com".into()],
+            url: "https://example.com".into(),
+            address: "1 rue de Paris, 75001 Paris".into(),
+            note: "Ligne 1\nLigne 2".into(),
+            format,
+        }
+    }
+
+    #[test]
+    fn test_vcard_payload() {
+        let payload = contact_payload(&contact(ContactFormat::VCard)).unwrap();
+        let lines: Vec<&str> = payload.split("\r\n").collect();
+        assert_eq!(lines[0], "BEGIN:VCARD");
+        assert_eq!(lines[1], "VERSION:3.0");
+        assert!(lines.contains(&"N:Dupont;Jeanne;;;"));
+        assert!(lines.contains(&"FN:Jeanne Dupont"));
+        assert!(lines.contains(&"ORG:ACME\\; Inc"));
+        assert!(lines.contains(&"TITLE:Directrice"));
+        assert!(lines.contains(&"ADR:;;1 rue de Paris\\, 75001 Paris;;;;"));
+        assert!(lines.contains(&"NOTE:Ligne 1\\nLigne 2"));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("TEL:")).count(), 1);
+        assert_eq!(lines.last(), Some(&"END:VCARD"));
+    }
+
+    #[test]
+    fn test_mecard_payload() {
+        let payload = contact_payload(&contact(ContactFormat::MeCard)).unwrap();
+        assert!(payload.starts_with("MECARD:N:Dupont,Jeanne;ORG:ACME\\; Inc;"));
+        assert!(payload.contains("TEL:+33 1 23 45 67 89;"));
+        assert!(payload.contains("URL:https\\://example.com;"));
+        assert!(!payload.contains("Directrice"));
+        assert!(payload.ends_with(";;"));
+    }
+
+    #[test]
+    fn test_contact_requires_name_or_organisation() {
+        assert!(contact_payload(&ContactPayload::default()).is_err());
+    }
+
+    #[test]
+    fn test_content_round_trips_through_json() {
+        let content = QrContent::Wifi(wifi("Bureau", WifiSecurity::Wpa, "secret", true));
+        let json = serde_json::to_string(&content).unwrap();
+        assert!(json.contains("\"type\":\"wifi\""));
+        assert_eq!(serde_json::from_str::<QrContent>(&json).unwrap(), content);
+    }
+}
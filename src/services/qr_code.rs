@@ -1,124 +1,723 @@
-use dioxus::prelude::*;
 /// Render a QR code into PNG bytes.
 use image::ImageEncoder;
-#[allow(unused_imports)]
-use std::fs;
-#[allow(unused_imports)]
-use std::path::Path;
-
-use crate::models::qr_code::{MarginEnabled, SavedQr};
-
-#[server(GenerateQrCode)]
-pub async fn generate_qr_code(
-    text: String,
-    size: u32,
-    transparent: bool,
-    margin: MarginEnabled,
-) -> Result<String, ServerFnError> {
-    let bytes =
-        render_qr_png_bytes(&text, size, transparent, margin).map_err(|e| ServerFnError::new(e))?;
-    let base64_image = base64::encode(&bytes);
-    let data_url = format!("data:image/png;base64,{}", base64_image);
-    Ok(data_url)
-}
-
-#[server(SaveQr)]
-pub async fn save_qr(qr: SavedQr) -> Result<String, ServerFnError> {
-    // ensure data dir
-    let data_dir = Path::new("data");
-    if !data_dir.exists() {
-        fs::create_dir_all(data_dir).map_err(|e| ServerFnError::new(e.to_string()))?;
-    }
-
-    let filename = format!("data/{}.json", qr.id);
-    let json = serde_json::to_string_pretty(&qr).map_err(|e| ServerFnError::new(e.to_string()))?;
-    fs::write(&filename, json).map_err(|e| ServerFnError::new(e.to_string()))?;
-    Ok(filename)
-}
-
-#[server(ListSaved)]
-pub async fn list_saved() -> Result<Vec<SavedQr>, ServerFnError> {
-    let data_dir = Path::new("data");
-    if !data_dir.exists() {
-        return Ok(vec![]);
-    }
-    let mut res = vec![];
-    for entry in fs::read_dir(data_dir).map_err(|e| ServerFnError::new(e.to_string()))? {
-        let entry = entry.map_err(|e| ServerFnError::new(e.to_string()))?;
-        let path = entry.path();
-        if let Some(extension) = path.extension() {
-            if extension == "json" {
-                match fs::read_to_string(&path) {
-                    Ok(s) => match serde_json::from_str::<SavedQr>(&s) {
-                        Ok(qr) => res.push(qr),
-                        Err(e) => {
-                            // Log l'erreur mais continue avec les autres fichiers
-                            eprintln!("Erreur de désérialisation du fichier {:?}: {}", path, e);
+
+use crate::models::qr_code::{
+    ErrorCorrection, FinderStyle, ModuleStyle, QrColor, QrLogo, RenderOptions, RenderedQr,
+};
+
+/// Encode le texte avec le niveau de correction d'erreur demandé.
+fn build_qr_code(text: &str, error_correction: ErrorCorrection) -> Result<qrcode::QrCode, String> {
+    let level = match error_correction {
+        ErrorCorrection::L => qrcode::EcLevel::L,
+        ErrorCorrection::M => qrcode::EcLevel::M,
+        ErrorCorrection::Q => qrcode::EcLevel::Q,
+        ErrorCorrection::H => qrcode::EcLevel::H,
+    };
+    qrcode::QrCode::with_error_correction_level(text.as_bytes(), level).map_err(|e| e.to_string())
+}
+
+/// Occupation de la capacité du QR code pour un contenu donné
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapacityInfo {
+    /// Version du symbole (1 à 40)
+    pub version: i16,
+    /// Modules par côté, hors marge
+    pub modules: usize,
+    /// Octets du contenu encodé
+    pub used_bytes: usize,
+    /// Octets maximum en mode binaire (version 40) au niveau de correction choisi
+    pub max_bytes: usize,
+}
+
+impl CapacityInfo {
+    /// Au-delà, le code devient dense et difficile à lire en petit format
+    pub const DENSE_VERSION: i16 = 15;
+
+    pub fn is_dense(&self) -> bool {
+        self.version >= Self::DENSE_VERSION
+    }
+}
+
+/// Capacité maximale en octets (version 40, mode binaire) par niveau de correction.
+fn max_capacity(error_correction: ErrorCorrection) -> usize {
+    match error_correction {
+        ErrorCorrection::L => 2953,
+        ErrorCorrection::M => 2331,
+        ErrorCorrection::Q => 1663,
+        ErrorCorrection::H => 1273,
+    }
+}
+
+/// Calcule la version nécessaire pour encoder le texte.
+pub fn capacity_info(
+    text: &str,
+    error_correction: ErrorCorrection,
+) -> Result<CapacityInfo, String> {
+    let max_bytes = max_capacity(error_correction);
+    let code = build_qr_code(text, error_correction).map_err(|_| {
+        format!(
+            "Contenu trop long ({} octets, maximum {} octets)",
+            text.len(),
+            max_bytes
+        )
+    })?;
+    let version = match code.version() {
+        qrcode::Version::Normal(v) | qrcode::Version::Micro(v) => v,
+    };
+    Ok(CapacityInfo {
+        version,
+        modules: code.width(),
+        used_bytes: text.len(),
+        max_bytes,
+    })
+}
+
+/// Taille maximale acceptée pour un logo (avant encodage base64).
+pub const MAX_LOGO_BYTES: usize = 512 * 1024;
+
+/// Décode et valide un logo PNG ou JPEG encodé en base64, avec son type MIME.
+pub fn decode_logo(logo: &QrLogo) -> Result<(image::DynamicImage, &'static str), String> {
+    let bytes = base64::decode(logo.data.trim()).map_err(|e| e.to_string())?;
+    if bytes.len() > MAX_LOGO_BYTES {
+        return Err(format!(
+            "Logo trop volumineux ({} Ko, maximum {} Ko)",
+            bytes.len() / 1024,
+            MAX_LOGO_BYTES / 1024
+        ));
+    }
+    let format = image::guess_format(&bytes).map_err(|e| e.to_string())?;
+    let mime = match format {
+        image::ImageFormat::Png => "image/png",
+        image::ImageFormat::Jpeg => "image/jpeg",
+        _ => return Err("Format de logo non supporté (PNG ou JPEG attendu)".into()),
+    };
+    let image = image::load_from_memory_with_format(&bytes, format).map_err(|e| e.to_string())?;
+    Ok((image, mime))
+}
+
+/// Refuse un logo dont le cadre dépasse la marge maximale.
+pub fn check_logo(logo: &QrLogo) -> Result<(), String> {
+    if logo.padding_percent > QrLogo::MAX_PADDING_PERCENT {
+        return Err(format!(
+            "Cadre du logo trop large : {} % (maximum {} %)",
+            logo.padding_percent,
+            QrLogo::MAX_PADDING_PERCENT
+        ));
+    }
+    Ok(())
+}
+
+/// Position du logo dans une image carrée de côté `width` : `(x, y, largeur, hauteur, marge)`.
+fn logo_box(
+    width: u32,
+    logo: &QrLogo,
+    logo_image: &image::DynamicImage,
+) -> (u32, u32, u32, u32, u32) {
+    let scale = logo.scale_percent.clamp(1, QrLogo::MAX_SCALE_PERCENT);
+    let max_side = (width * scale / 100).max(1);
+    let padding = width * logo.padding_percent.min(QrLogo::MAX_PADDING_PERCENT) / 100;
+    let (w, h) = (logo_image.width().max(1), logo_image.height().max(1));
+    let (logo_w, logo_h) = if w >= h {
+        (max_side, (max_side * h / w).max(1))
+    } else {
+        ((max_side * w / h).max(1), max_side)
+    };
+    let x = (width - logo_w) / 2;
+    let y = (width - logo_h) / 2;
+    (x, y, logo_w, logo_h, padding)
+}
+
+/// Incruste le logo au centre de l'image, sur un cadre de la couleur de fond.
+fn overlay_logo(
+    image: &mut image::RgbaImage,
+    logo: &QrLogo,
+    background: QrColor,
+) -> Result<(), String> {
+    let (logo_image, _) = decode_logo(logo)?;
+    let (x, y, logo_w, logo_h, padding) = logo_box(image.width(), logo, &logo_image);
+
+    let box_x = x.saturating_sub(padding);
+    let box_y = y.saturating_sub(padding);
+    let box_right = (x + logo_w + padding).min(image.width());
+    let box_bottom = (y + logo_h + padding).min(image.height());
+    for py in box_y..box_bottom {
+        for px in box_x..box_right {
+            image.put_pixel(px, py, image::Rgba(background.0));
+        }
+    }
+
+    let resized = logo_image
+        .resize_exact(logo_w, logo_h, image::imageops::FilterType::Lanczos3)
+        .to_rgba8();
+    image::imageops::overlay(image, &resized, x as i64, y as i64);
+    Ok(())
+}
+
+/// Vérifie qu'un rendu se relit bien : le contenu décodé doit être identique au texte.
+pub fn verify_scannable(text: &str, image: &image::RgbaImage) -> bool {
+    decode_image(image).iter().any(|content| content == text)
+}
+
+/// Refuse d'enregistrer un QR code dont le rendu ne se relit pas, sauf si
+/// `force` est demandé ; un rendu impossible est toujours refusé.
+pub fn check_scannable(text: &str, options: &RenderOptions, force: bool) -> Result<(), String> {
+    let image = render_qr_image(text, options)?;
+    if !force && !verify_scannable(text, &image) {
+        return Err("Ce QR code ne se relit pas : corrigez-le ou forcez l'enregistrement.".into());
+    }
+    Ok(())
+}
+
+/// Taille maximale acceptée pour une image à décoder.
+pub const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+/// Nombre maximal de pixels accepté pour une image à décoder.
+pub const MAX_IMPORT_PIXELS: u64 = 4096 * 4096;
+
+/// Convertit une image en niveaux de gris, la transparence étant composée sur du blanc.
+fn flatten_to_luma(rgba: &image::RgbaImage) -> image::GrayImage {
+    image::GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        let alpha = a as f32 / 255.0;
+        image::Luma([(luma * alpha + 255.0 * (1.0 - alpha)).round() as u8])
+    })
+}
+
+/// Décode tous les QR codes présents dans une image.
+fn decode_image(image: &image::RgbaImage) -> Vec<String> {
+    let luma = flatten_to_luma(image);
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        luma.width() as usize,
+        luma.height() as usize,
+        |x, y| luma.get_pixel(x as u32, y as u32).0[0],
+    );
+    prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_, content)| content)
+        .collect()
+}
+
+/// Décode les QR codes d'une image PNG ou JPEG importée.
+pub fn decode_qr_codes(bytes: &[u8]) -> Result<Vec<String>, String> {
+    if bytes.len() > MAX_IMPORT_BYTES {
+        return Err(format!(
+            "Image trop volumineuse ({} Mo, maximum {} Mo)",
+            bytes.len() / (1024 * 1024),
+            MAX_IMPORT_BYTES / (1024 * 1024)
+        ));
+    }
+    let format = image::guess_format(bytes).map_err(|e| e.to_string())?;
+    if !matches!(format, image::ImageFormat::Png | image::ImageFormat::Jpeg) {
+        return Err("Format d'image non supporté (PNG ou JPEG attendu)".into());
+    }
+    let (width, height) = image::ImageReader::with_format(std::io::Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|e| e.to_string())?;
+    if width as u64 * height as u64 > MAX_IMPORT_PIXELS {
+        return Err(format!(
+            "Image trop grande ({}×{} pixels, maximum {} mégapixels)",
+            width,
+            height,
+            MAX_IMPORT_PIXELS / 1_000_000
+        ));
+    }
+    let image = image::load_from_memory_with_format(bytes, format).map_err(|e| e.to_string())?;
+    let contents = decode_image(&image.to_rgba8());
+    if contents.is_empty() {
+        return Err("Aucun QR code lisible dans cette image.".into());
+    }
+    Ok(contents)
+}
+
+/// Ratio de contraste minimal en dessous duquel le rendu est refusé.
+pub const MIN_CONTRAST_RATIO: f64 = 3.0;
+/// Ratio de contraste recommandé pour une lecture fiable.
+pub const RECOMMENDED_CONTRAST_RATIO: f64 = 4.5;
+
+/// Résultat de la vérification de contraste entre modules sombres et clairs
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContrastCheck {
+    /// Lecture fiable
+    Good,
+    /// Lisible par la plupart des lecteurs, mais risqué
+    Warning(String),
+    /// Illisible pour une majorité de lecteurs : le rendu est refusé
+    Refused(String),
+}
+
+/// Luminance relative (WCAG 2.x) d'une couleur composée sur un fond blanc.
+fn relative_luminance(color: QrColor) -> f64 {
+    let [r, g, b, a] = color.0;
+    let alpha = a as f64 / 255.0;
+    let channel = |c: u8| {
+        let blended = (c as f64 / 255.0) * alpha + (1.0 - alpha);
+        if blended <= 0.03928 {
+            blended / 12.92
+        } else {
+            ((blended + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// Ratio de contraste WCAG entre deux couleurs (1.0 à 21.0).
+pub fn contrast_ratio(a: QrColor, b: QrColor) -> f64 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    let (light, dark) = if la > lb { (la, lb) } else { (lb, la) };
+    (light + 0.05) / (dark + 0.05)
+}
+
+/// Vérifie qu'un couple premier plan / arrière-plan reste lisible par les scanners.
+pub fn check_contrast(foreground: QrColor, background: QrColor) -> ContrastCheck {
+    let ratio = contrast_ratio(foreground, background);
+    if ratio < MIN_CONTRAST_RATIO {
+        return ContrastCheck::Refused(format!(
+            "Contraste insuffisant ({:.1}:1, minimum {:.1}:1)",
+            ratio, MIN_CONTRAST_RATIO
+        ));
+    }
+    if relative_luminance(foreground) > relative_luminance(background) {
+        return ContrastCheck::Warning(
+            "Couleurs inversées (modules clairs sur fond sombre) : certains lecteurs ne les reconnaissent pas".into(),
+        );
+    }
+    if ratio < RECOMMENDED_CONTRAST_RATIO {
+        return ContrastCheck::Warning(format!(
+            "Contraste faible ({:.1}:1, recommandé {:.1}:1)",
+            ratio, RECOMMENDED_CONTRAST_RATIO
+        ));
+    }
+    ContrastCheck::Good
+}
+
+/// Couleurs effectives des modules sombres et clairs après vérification du contraste.
+fn resolve_colors(options: &RenderOptions) -> Result<(QrColor, QrColor), String> {
+    if let ContrastCheck::Refused(reason) = check_contrast(options.foreground, options.background) {
+        return Err(reason);
+    }
+    let background = if options.transparent {
+        options.background.with_alpha(0)
+    } else {
+        options.background
+    };
+    Ok((options.foreground, background))
+}
+
+/// Sous-échantillons par axe et par pixel pour l'anticrénelage du rastériseur.
+const SUPERSAMPLING: u32 = 4;
+
+/// Dimensions du rendu, calculées comme `qrcode::render::Renderer::min_dimensions`.
+struct Layout {
+    /// Modules par côté, marge comprise
+    modules: u32,
+    /// Côté d'un module en pixels
+    unit: u32,
+    /// Marge en modules
+    quiet_zone: u32,
+}
+
+impl Layout {
+    fn new(code_width: u32, options: &RenderOptions) -> Self {
+        let quiet_zone = if options.margin.0 { 4 } else { 0 };
+        let modules = code_width + 2 * quiet_zone;
+        let unit = options.size.div_ceil(modules).max(1);
+        Layout {
+            modules,
+            unit,
+            quiet_zone,
+        }
+    }
+
+    fn pixels(&self) -> u32 {
+        self.modules * self.unit
+    }
+}
+
+/// Rectangle aux coins arrondis, en modules (rayons : haut-gauche, haut-droit, bas-droit, bas-gauche).
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RoundRect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    radii: [f64; 4],
+}
+
+impl RoundRect {
+    fn new(x: f64, y: f64, w: f64, h: f64, radius: f64) -> Self {
+        RoundRect {
+            x,
+            y,
+            w,
+            h,
+            radii: [radius; 4],
+        }
+    }
+
+    fn contains(&self, px: f64, py: f64) -> bool {
+        let (right, bottom) = (self.x + self.w, self.y + self.h);
+        if px < self.x || py < self.y || px > right || py > bottom {
+            return false;
+        }
+        let [tl, tr, br, bl] = self.radii;
+        let corners = [
+            (
+                self.x + tl,
+                self.y + tl,
+                tl,
+                px < self.x + tl && py < self.y + tl,
+            ),
+            (
+                right - tr,
+                self.y + tr,
+                tr,
+                px > right - tr && py < self.y + tr,
+            ),
+            (
+                right - br,
+                bottom - br,
+                br,
+                px > right - br && py > bottom - br,
+            ),
+            (
+                self.x + bl,
+                bottom - bl,
+                bl,
+                px < self.x + bl && py > bottom - bl,
+            ),
+        ];
+        corners.iter().all(|&(cx, cy, r, in_corner)| {
+            !in_corner || (px - cx).powi(2) + (py - cy).powi(2) <= r * r
+        })
+    }
+
+    /// Tracé SVG du contour, converti en pixels.
+    fn svg_path(&self, layout: &Layout) -> String {
+        let unit = layout.unit as f64;
+        let offset = layout.quiet_zone as f64;
+        let px = |v: f64| svg_number((v + offset) * unit);
+        let len = |v: f64| svg_number(v * unit);
+        let [tl, tr, br, bl] = self.radii;
+        let (right, bottom) = (self.x + self.w, self.y + self.h);
+        let arc = |r: f64, x: f64, y: f64| {
+            if r > 0.0 {
+                format!("A{r} {r} 0 0 1 {x} {y}", r = len(r), x = px(x), y = px(y))
+            } else {
+                String::new()
+            }
+        };
+        format!(
+            "M{} {}H{}{}V{}{}H{}{}V{}{}Z",
+            px(self.x + tl),
+            px(self.y),
+            px(right - tr),
+            arc(tr, right, self.y + tr),
+            px(bottom - br),
+            arc(br, right - br, bottom),
+            px(self.x + bl),
+            arc(bl, self.x, bottom - bl),
+            px(self.y + tl),
+            arc(tl, self.x + tl, self.y),
+        )
+    }
+}
+
+/// Forme élémentaire dessinée en couleur de premier plan.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Fill(RoundRect),
+    /// Anneau : `outer` privé de `inner`
+    Ring {
+        outer: RoundRect,
+        inner: RoundRect,
+    },
+}
+
+impl Shape {
+    fn bounds(&self) -> RoundRect {
+        match self {
+            Shape::Fill(rect) => *rect,
+            Shape::Ring { outer, .. } => *outer,
+        }
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            Shape::Fill(rect) => rect.contains(x, y),
+            Shape::Ring { outer, inner } => outer.contains(x, y) && !inner.contains(x, y),
+        }
+    }
+
+    fn svg_path(&self, layout: &Layout) -> String {
+        match self {
+            Shape::Fill(rect) => rect.svg_path(layout),
+            Shape::Ring { outer, inner } => {
+                format!("{}{}", outer.svg_path(layout), inner.svg_path(layout))
+            }
+        }
+    }
+}
+
+/// Origines (en modules) des trois motifs de repérage.
+fn finder_origins(width: u32) -> [(u32, u32); 3] {
+    [(0, 0), (width - 7, 0), (0, width - 7)]
+}
+
+fn in_finder(width: u32, x: u32, y: u32) -> bool {
+    finder_origins(width)
+        .iter()
+        .any(|&(fx, fy)| (fx..fx + 7).contains(&x) && (fy..fy + 7).contains(&y))
+}
+
+/// Décompose le QR code en formes selon les styles choisis.
+fn qr_shapes(
+    code: &qrcode::QrCode,
+    module_style: ModuleStyle,
+    finder_style: FinderStyle,
+) -> Vec<Shape> {
+    let width = code.width() as u32;
+    let dark = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && (x as u32) < width
+            && (y as u32) < width
+            && !in_finder(width, x as u32, y as u32)
+            && code[(x as usize, y as usize)] == qrcode::Color::Dark
+    };
+    let mut shapes = Vec::new();
+
+    let (outer_r, inner_r, ball_r) = match finder_style {
+        FinderStyle::Square => (0.0, 0.0, 0.0),
+        FinderStyle::Rounded => (2.0, 1.2, 0.9),
+        FinderStyle::Circle => (3.5, 2.5, 1.5),
+    };
+    for (fx, fy) in finder_origins(width) {
+        let (fx, fy) = (fx as f64, fy as f64);
+        shapes.push(Shape::Ring {
+            outer: RoundRect::new(fx, fy, 7.0, 7.0, outer_r),
+            inner: RoundRect::new(fx + 1.0, fy + 1.0, 5.0, 5.0, inner_r),
+        });
+        shapes.push(Shape::Fill(RoundRect::new(
+            fx + 2.0,
+            fy + 2.0,
+            3.0,
+            3.0,
+            ball_r,
+        )));
+    }
+
+    match module_style {
+        ModuleStyle::VerticalBars | ModuleStyle::HorizontalBars => {
+            let vertical = module_style == ModuleStyle::VerticalBars;
+            for line in 0..width as i64 {
+                let mut pos = 0i64;
+                while pos < width as i64 {
+                    let at = |p: i64| {
+                        if vertical {
+                            dark(line, p)
+                        } else {
+                            dark(p, line)
                         }
-                    },
-                    Err(e) => {
-                        // Log l'erreur mais continue
-                        eprintln!("Erreur de lecture du fichier {:?}: {}", path, e);
+                    };
+                    if !at(pos) {
+                        pos += 1;
+                        continue;
+                    }
+                    let start = pos;
+                    while pos < width as i64 && at(pos) {
+                        pos += 1;
+                    }
+                    let (line, start, len) = (line as f64, start as f64, (pos - start) as f64);
+                    shapes.push(Shape::Fill(if vertical {
+                        RoundRect::new(line + 0.1, start, 0.8, len, 0.4)
+                    } else {
+                        RoundRect::new(start, line + 0.1, len, 0.8, 0.4)
+                    }));
+                }
+            }
+        }
+        _ => {
+            for y in 0..width as i64 {
+                for x in 0..width as i64 {
+                    if !dark(x, y) {
+                        continue;
                     }
+                    let (fx, fy) = (x as f64, y as f64);
+                    let rect = match module_style {
+                        ModuleStyle::Circle => RoundRect::new(fx + 0.05, fy + 0.05, 0.9, 0.9, 0.45),
+                        ModuleStyle::Rounded => {
+                            // Seuls les coins sans voisin sombre sont arrondis
+                            let (top, right) = (dark(x, y - 1), dark(x + 1, y));
+                            let (bottom, left) = (dark(x, y + 1), dark(x - 1, y));
+                            let r = |a: bool, b: bool| if a || b { 0.0 } else { 0.5 };
+                            RoundRect {
+                                x: fx,
+                                y: fy,
+                                w: 1.0,
+                                h: 1.0,
+                                radii: [
+                                    r(top, left),
+                                    r(top, right),
+                                    r(bottom, right),
+                                    r(bottom, left),
+                                ],
+                            }
+                        }
+                        _ => RoundRect::new(fx, fy, 1.0, 1.0, 0.0),
+                    };
+                    shapes.push(Shape::Fill(rect));
                 }
             }
         }
     }
-    Ok(res)
+    shapes
 }
 
-#[server(LoadSaved)]
-pub async fn load_saved(filename: String) -> Result<SavedQr, ServerFnError> {
-    let path = Path::new("data").join(filename);
-    let s = fs::read_to_string(&path).map_err(|e| ServerFnError::new(e.to_string()))?;
-    let qr: SavedQr = serde_json::from_str(&s).map_err(|e| ServerFnError::new(e.to_string()))?;
-    Ok(qr)
+/// Mélange les couleurs claire et sombre selon la couverture (alpha prémultiplié).
+fn blend(dark: QrColor, light: QrColor, coverage: f32) -> image::Rgba<u8> {
+    let coverage = coverage.clamp(0.0, 1.0);
+    let (da, la) = (dark.0[3] as f32 / 255.0, light.0[3] as f32 / 255.0);
+    let alpha = da * coverage + la * (1.0 - coverage);
+    if alpha <= 0.0 {
+        return image::Rgba([light.0[0], light.0[1], light.0[2], 0]);
+    }
+    let channel = |i: usize| {
+        let value =
+            (dark.0[i] as f32 * da * coverage + light.0[i] as f32 * la * (1.0 - coverage)) / alpha;
+        value.round().clamp(0.0, 255.0) as u8
+    };
+    image::Rgba([
+        channel(0),
+        channel(1),
+        channel(2),
+        (alpha * 255.0).round() as u8,
+    ])
 }
 
-#[server(DeleteSaved)]
-pub async fn delete_saved(filename: String) -> Result<(), ServerFnError> {
-    let path = Path::new("data").join(format!("{}.json", filename));
-    if !path.exists() {
-        return Err(ServerFnError::new(format!(
-            "Le fichier {} n'existe pas",
-            filename
-        )));
+/// Rastérise les formes du QR code avec anticrénelage.
+fn rasterize(
+    code: &qrcode::QrCode,
+    options: &RenderOptions,
+    dark: QrColor,
+    light: QrColor,
+) -> image::RgbaImage {
+    let layout = Layout::new(code.width() as u32, options);
+    let side = layout.pixels();
+    let unit = layout.unit as f64;
+    let offset = layout.quiet_zone as f64;
+    let samples = (SUPERSAMPLING * SUPERSAMPLING) as f32;
+    let mut coverage = vec![0f32; (side * side) as usize];
+
+    for shape in qr_shapes(code, options.module_style, options.finder_style) {
+        let bounds = shape.bounds();
+        let to_px = |v: f64| ((v + offset) * unit).max(0.0);
+        let (x0, y0) = (
+            to_px(bounds.x).floor() as u32,
+            to_px(bounds.y).floor() as u32,
+        );
+        let x1 = (to_px(bounds.x + bounds.w).ceil() as u32).min(side);
+        let y1 = (to_px(bounds.y + bounds.h).ceil() as u32).min(side);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let mut hits = 0u32;
+                for sy in 0..SUPERSAMPLING {
+                    for sx in 0..SUPERSAMPLING {
+                        let mx = (px as f64 + (sx as f64 + 0.5) / SUPERSAMPLING as f64) / unit;
+                        let my = (py as f64 + (sy as f64 + 0.5) / SUPERSAMPLING as f64) / unit;
+                        if shape.contains(mx - offset, my - offset) {
+                            hits += 1;
+                        }
+                    }
+                }
+                if hits > 0 {
+                    let cell = &mut coverage[(py * side + px) as usize];
+                    *cell = (*cell + hits as f32 / samples).min(1.0);
+                }
+            }
+        }
     }
-    fs::remove_file(&path).map_err(|e| ServerFnError::new(e.to_string()))?;
-    Ok(())
+
+    image::RgbaImage::from_fn(side, side, |x, y| {
+        blend(dark, light, coverage[(y * side + x) as usize])
+    })
 }
 
-#[allow(dead_code)]
-pub fn render_qr_png_bytes(
-    text: &str,
-    size: u32,
-    transparent: bool,
-    margin: MarginEnabled,
-) -> Result<Vec<u8>, String> {
+/// Rastérise le QR code (logo compris) sans l'encoder.
+pub fn render_qr_image(text: &str, options: &RenderOptions) -> Result<image::RgbaImage, String> {
     if text.is_empty() {
         return Err("Le texte ne peut pas être vide.".into());
     }
 
-    let image = qrcode::QrCode::new(text.as_bytes())
-        .map_err(|e| e.to_string())?
-        .render()
-        .dark_color(image::Rgba([0, 0, 0, 255]))
-        .light_color(image::Rgba([
-            255,
-            255,
-            255,
-            if transparent { 0 } else { 255 },
-        ]))
-        .quiet_zone(margin.0) // Utilise directement le boolean
-        .min_dimensions(size, size)
-        .build();
+    let (dark, light) = resolve_colors(options)?;
+    let code = build_qr_code(text, options.effective_error_correction())?;
+    let mut image = rasterize(&code, options, dark, light);
+
+    if let Some(logo) = &options.logo {
+        overlay_logo(&mut image, logo, light)?;
+    }
+    Ok(image)
+}
+
+pub fn render_qr_png_bytes(text: &str, options: &RenderOptions) -> Result<Vec<u8>, String> {
+    let image = render_qr_image(text, options)?;
+    encode_png(image)
+}
+
+/// Hachage FNV-1a 64 bits, identique d'une version de Rust à l'autre.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.into_iter().fold(OFFSET, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// Empreinte des paramètres de génération : deux rendus de même empreinte
+/// produisent la même image. Stable entre deux builds, elle peut servir d'ETag.
+pub fn render_fingerprint(text: &str, options: &RenderOptions) -> u64 {
+    let options = serde_json::to_string(options).unwrap_or_default();
+    // La longueur du texte sépare les deux champs sans ambiguïté
+    fnv1a(
+        (text.len() as u64)
+            .to_le_bytes()
+            .into_iter()
+            .chain(text.bytes())
+            .chain(options.bytes()),
+    )
+}
+
+/// Côté maximal d'un aperçu rendu par le client (WASM) ; au-delà, le rendu
+/// reste confié au serveur.
+pub const LOCAL_PREVIEW_MAX_SIZE: u32 = 512;
+
+/// Indique si l'aperçu peut être rendu dans le navigateur : le logo (décodage
+/// et redimensionnement d'image) et les grandes tailles passent par le serveur.
+pub fn local_preview_supported(options: &RenderOptions) -> bool {
+    options.logo.is_none() && options.size <= LOCAL_PREVIEW_MAX_SIZE
+}
 
+/// Aperçu PNG en data URL, avec le résultat de la relecture.
+pub fn render_preview(text: &str, options: &RenderOptions) -> Result<RenderedQr, String> {
+    let image = render_qr_image(text, options)?;
+    let scannable = verify_scannable(text, &image);
+    let bytes = encode_png(image)?;
+    let data_url = format!("data:image/png;base64,{}", base64::encode(&bytes));
+    Ok(RenderedQr {
+        data_url,
+        scannable,
+    })
+}
+
+/// Encode une image RGBA en PNG.
+fn encode_png(image: image::RgbaImage) -> Result<Vec<u8>, String> {
     let mut buffer = Vec::new();
     let width = image.width();
     let height = image.height();
 
-    // Consume the image and get the raw container. For the qrcode crate the
-    // subpixel type is u8 so this should be Vec<u8>, enabling zero-copy encoding.
+    // Consume the image and get the raw container. The subpixel type is u8 so
+    // this is a Vec<u8>, enabling zero-copy encoding.
     let raw = image.into_raw();
 
     // Determine channels per pixel (must divide evenly)
@@ -146,14 +745,107 @@ pub fn render_qr_png_bytes(
     Ok(buffer)
 }
 
+/// Render a QR code into an SVG document (vector output, mêmes options que le PNG).
+pub fn render_qr_svg(text: &str, options: &RenderOptions) -> Result<String, String> {
+    if text.is_empty() {
+        return Err("Le texte ne peut pas être vide.".into());
+    }
+
+    let (dark, light) = resolve_colors(options)?;
+    let code = build_qr_code(text, options.effective_error_correction())?;
+    let layout = Layout::new(code.width() as u32, options);
+    let width = layout.pixels();
+    let (dark_fill, light_fill) = (svg_color(dark), svg_color(light));
+    let crisp =
+        options.module_style == ModuleStyle::Square && options.finder_style == FinderStyle::Square;
+
+    let path: String = qr_shapes(&code, options.module_style, options.finder_style)
+        .iter()
+        .map(|shape| shape.svg_path(&layout))
+        .collect();
+    let mut svg = format!(
+        concat!(
+            r#"<?xml version="1.0" standalone="yes"?>"#,
+            r#"<svg xmlns="http://www.w3.org/2000/svg""#,
+            r#" version="1.1" width="{w}" height="{w}" viewBox="0 0 {w} {w}"{crisp}>"#,
+            r#"<rect x="0" y="0" width="{w}" height="{w}" fill="{bg}"/>"#,
+            r#"<path fill="{fg}" fill-rule="evenodd" d="{path}"/>"#,
+        ),
+        w = width,
+        crisp = if crisp {
+            r#" shape-rendering="crispEdges""#
+        } else {
+            ""
+        },
+        bg = light_fill,
+        fg = dark_fill,
+        path = path,
+    );
+
+    if let Some(logo) = &options.logo {
+        let (logo_image, mime) = decode_logo(logo)?;
+        let (x, y, logo_w, logo_h, padding) = logo_box(width, logo, &logo_image);
+        svg.push_str(&format!(
+            concat!(
+                r#"<rect x="{bx}" y="{by}" width="{bw}" height="{bh}" fill="{bg}"/>"#,
+                r#"<image x="{x}" y="{y}" width="{w}" height="{h}" preserveAspectRatio="none""#,
+                r#" href="data:{mime};base64,{data}"/>"#,
+            ),
+            bx = x.saturating_sub(padding),
+            by = y.saturating_sub(padding),
+            bw = logo_w + 2 * padding,
+            bh = logo_h + 2 * padding,
+            bg = light_fill,
+            x = x,
+            y = y,
+            w = logo_w,
+            h = logo_h,
+            mime = mime,
+            data = logo.data.trim(),
+        ));
+    }
+    svg.push_str("</svg>");
+
+    Ok(svg)
+}
+
+/// Nombre SVG compact (trois décimales au plus, sans zéros superflus).
+fn svg_number(value: f64) -> String {
+    let formatted = format!("{:.3}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Couleur de remplissage SVG (`none` pour une couleur totalement transparente).
+fn svg_color(color: QrColor) -> String {
+    let [r, g, b, a] = color.0;
+    match a {
+        0 => "none".into(),
+        255 => color.to_rgb_hex(),
+        _ => format!("rgba({},{},{},{:.3})", r, g, b, a as f64 / 255.0),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::qr_code::{MarginEnabled, SavedQr};
+
+    fn options(size: u32, transparent: bool, margin: bool) -> RenderOptions {
+        RenderOptions {
+            size,
+            transparent,
+            margin: MarginEnabled(margin),
+            ..RenderOptions::default()
+        }
+    }
 
     #[test]
     fn test_render_qr_png_bytes_basic() {
         let bytes =
-            render_qr_png_bytes("hello", 128, false, MarginEnabled(true)).expect("render failed");
+            render_qr_png_bytes("hello", &options(128, false, true)).expect("render failed");
         // PNG magic bytes: 89 50 4E 47 0D 0A 1A 0A
         let png_magic = [0x89u8, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
         assert!(bytes.len() >= png_magic.len());
@@ -162,8 +854,8 @@ mod tests {
 
     #[test]
     fn test_render_qr_png_bytes_transparent() {
-        let bytes = render_qr_png_bytes("transparent", 128, true, MarginEnabled(true))
-            .expect("render failed");
+        let bytes =
+            render_qr_png_bytes("transparent", &options(128, true, true)).expect("render failed");
         let png_magic = [0x89u8, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
         assert_eq!(&bytes[0..8], &png_magic);
     }
@@ -185,5 +877,322 @@ mod tests {
         assert!(!t.is_empty());
     }
 
+    #[test]
+    fn test_render_qr_svg_basic() {
+        let svg = render_qr_svg("hello", &options(128, false, true)).expect("render failed");
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("fill=\"#ffffff\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_render_qr_svg_transparent() {
+        let svg = render_qr_svg("transparent", &options(128, true, false)).expect("render failed");
+        assert!(svg.contains("fill=\"none\""));
+    }
+
+    #[test]
+    fn test_render_qr_svg_empty_text() {
+        assert!(render_qr_svg("", &options(128, false, true)).is_err());
+    }
+
+    #[test]
+    fn test_error_correction_level_applied() {
+        let low = build_qr_code("https://example.com/some/long/path", ErrorCorrection::L).unwrap();
+        let high = build_qr_code("https://example.com/some/long/path", ErrorCorrection::H).unwrap();
+        assert_eq!(low.error_correction_level(), qrcode::EcLevel::L);
+        assert_eq!(high.error_correction_level(), qrcode::EcLevel::H);
+        assert!(high.width() > low.width());
+    }
+
+    #[test]
+    fn test_saved_qr_without_error_correction_loads() {
+        let json = r#"{
+            "id": "qr-1",
+            "text": "hello",
+            "size": 256,
+            "transparent": false,
+            "margin": true,
+            "created_at": "0",
+            "image_data": ""
+        }"#;
+        let qr: SavedQr = serde_json::from_str(json).expect("old file should load");
+        assert_eq!(qr.error_correction, ErrorCorrection::M);
+        assert_eq!(qr.foreground, QrColor::BLACK);
+        assert_eq!(qr.background, QrColor::WHITE);
+    }
+
+    #[test]
+    fn test_qr_color_hex_round_trip() {
+        assert_eq!(
+            QrColor::parse_hex("#1a2b3c"),
+            Some(QrColor([0x1a, 0x2b, 0x3c, 255]))
+        );
+        assert_eq!(QrColor::parse_hex("fff"), Some(QrColor::WHITE));
+        assert_eq!(
+            QrColor::parse_hex("#1a2b3c80").map(|c| c.to_hex()),
+            Some("#1a2b3c80".to_string())
+        );
+        assert_eq!(QrColor::parse_hex("#12345"), None);
+        assert_eq!(QrColor::parse_hex("#zzzzzz"), None);
+    }
+
+    #[test]
+    fn test_check_contrast() {
+        assert_eq!(
+            check_contrast(QrColor::BLACK, QrColor::WHITE),
+            ContrastCheck::Good
+        );
+        let light_grey = QrColor([200, 200, 200, 255]);
+        assert!(matches!(
+            check_contrast(light_grey, QrColor::WHITE),
+            ContrastCheck::Refused(_)
+        ));
+        assert!(matches!(
+            check_contrast(QrColor::WHITE, QrColor::BLACK),
+            ContrastCheck::Warning(_)
+        ));
+    }
+
+    #[test]
+    fn test_render_refuses_low_contrast() {
+        let opts = RenderOptions {
+            foreground: QrColor([230, 230, 230, 255]),
+            ..RenderOptions::default()
+        };
+        assert!(render_qr_png_bytes("hello", &opts).is_err());
+        assert!(render_qr_svg("hello", &opts).is_err());
+    }
+
+    #[test]
+    fn test_render_uses_custom_colors() {
+        let opts = RenderOptions {
+            foreground: QrColor([0x12, 0x34, 0x56, 255]),
+            background: QrColor([0xff, 0xee, 0xdd, 255]),
+            ..RenderOptions::default()
+        };
+        let svg = render_qr_svg("colors", &opts).expect("render failed");
+        assert!(svg.contains("fill=\"#123456\""));
+        assert!(svg.contains("fill=\"#ffeedd\""));
+    }
+
+    fn sample_logo() -> QrLogo {
+        let logo = image::RgbaImage::from_pixel(40, 20, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut bytes)
+            .write_image(logo.as_raw(), 40, 20, image::ExtendedColorType::Rgba8)
+            .unwrap();
+        QrLogo::new(base64::encode(&bytes))
+    }
+
+    #[test]
+    fn test_logo_forces_high_error_correction() {
+        let opts = RenderOptions {
+            error_correction: ErrorCorrection::L,
+            logo: Some(sample_logo()),
+            ..RenderOptions::default()
+        };
+        assert_eq!(opts.effective_error_correction(), ErrorCorrection::H);
+    }
+
+    #[test]
+    fn test_local_preview_supported() {
+        assert!(local_preview_supported(&RenderOptions::default()));
+        let large = RenderOptions {
+            size: LOCAL_PREVIEW_MAX_SIZE + 1,
+            ..RenderOptions::default()
+        };
+        assert!(!local_preview_supported(&large));
+        let with_logo = RenderOptions {
+            logo: Some(sample_logo()),
+            ..RenderOptions::default()
+        };
+        assert!(!local_preview_supported(&with_logo));
+    }
+
+    #[test]
+    fn test_render_with_logo_paints_centre() {
+        let opts = RenderOptions {
+            size: 200,
+            logo: Some(sample_logo()),
+            ..RenderOptions::default()
+        };
+        let bytes = render_qr_png_bytes("https://example.com", &opts).expect("render failed");
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        let centre = decoded.get_pixel(decoded.width() / 2, decoded.height() / 2);
+        assert_eq!(centre.0, [255, 0, 0, 255]);
+
+        let svg = render_qr_svg("https://example.com", &opts).expect("render failed");
+        assert!(svg.contains("data:image/png;base64,"));
+    }
+
+    #[test]
+    fn test_logo_padding_clamped() {
+        let logo = QrLogo {
+            padding_percent: u32::MAX,
+            ..sample_logo()
+        };
+        assert!(check_logo(&logo).is_err());
+        assert!(check_logo(&sample_logo()).is_ok());
+
+        let (logo_image, _) = decode_logo(&logo).unwrap();
+        let (_, _, _, _, padding) = logo_box(200, &logo, &logo_image);
+        assert_eq!(padding, 200 * QrLogo::MAX_PADDING_PERCENT / 100);
+        let opts = RenderOptions {
+            size: 200,
+            logo: Some(logo),
+            ..RenderOptions::default()
+        };
+        assert!(render_qr_png_bytes("https://example.com", &opts).is_ok());
+        assert!(render_qr_svg("https://example.com", &opts).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_logo_rejected() {
+        let opts = RenderOptions {
+            logo: Some(QrLogo::new(base64::encode(b"not an image"))),
+            ..RenderOptions::default()
+        };
+        assert!(render_qr_png_bytes("hello", &opts).is_err());
+    }
+
+    #[test]
+    fn test_square_style_matches_qrcode_renderer() {
+        let opts = options(200, false, true);
+        let code = build_qr_code("https://example.com", ErrorCorrection::M).unwrap();
+        let expected = code
+            .render()
+            .dark_color(image::Rgba([0u8, 0, 0, 255]))
+            .light_color(image::Rgba([255u8, 255, 255, 255]))
+            .min_dimensions(200, 200)
+            .build();
+        let actual = rasterize(&code, &opts, QrColor::BLACK, QrColor::WHITE);
+        assert_eq!(actual.dimensions(), expected.dimensions());
+        assert!(actual.pixels().eq(expected.pixels()));
+    }
+
+    #[test]
+    fn test_circle_finder_clears_corners() {
+        let code = build_qr_code("finder", ErrorCorrection::M).unwrap();
+        let opts = RenderOptions {
+            finder_style: FinderStyle::Circle,
+            margin: MarginEnabled(false),
+            ..RenderOptions::default()
+        };
+        let image = rasterize(&code, &opts, QrColor::BLACK, QrColor::WHITE);
+        // Coin du motif de repérage : blanc en rond, noir en carré
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        let unit = image.width() / code.width() as u32;
+        let centre = unit * 7 / 2;
+        assert_eq!(image.get_pixel(centre, centre).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_bar_styles_merge_runs() {
+        let code = build_qr_code("https://example.com/bars", ErrorCorrection::M).unwrap();
+        let squares = qr_shapes(&code, ModuleStyle::Square, FinderStyle::Square).len();
+        let vertical = qr_shapes(&code, ModuleStyle::VerticalBars, FinderStyle::Square).len();
+        let horizontal = qr_shapes(&code, ModuleStyle::HorizontalBars, FinderStyle::Square).len();
+        assert!(vertical < squares);
+        assert!(horizontal < squares);
+    }
+
+    #[test]
+    fn test_svg_rounded_styles_use_arcs() {
+        let opts = RenderOptions {
+            module_style: ModuleStyle::Circle,
+            finder_style: FinderStyle::Rounded,
+            ..RenderOptions::default()
+        };
+        let svg = render_qr_svg("styles", &opts).expect("render failed");
+        assert!(svg.contains('A'));
+        assert!(!svg.contains("crispEdges"));
+    }
+
+    #[test]
+    fn test_capacity_info() {
+        let small = capacity_info("hello", ErrorCorrection::M).unwrap();
+        assert_eq!(small.version, 1);
+        assert_eq!(small.modules, 21);
+        assert!(!small.is_dense());
+
+        let long = "x".repeat(1000);
+        let large = capacity_info(&long, ErrorCorrection::M).unwrap();
+        assert!(large.is_dense());
+        assert_eq!(large.used_bytes, 1000);
+
+        assert!(capacity_info(&"x".repeat(3000), ErrorCorrection::H).is_err());
+    }
+
+    #[test]
+    fn test_decode_qr_codes_round_trip() {
+        let bytes = render_qr_png_bytes("https://example.com/import", &options(256, false, true))
+            .expect("render failed");
+        let contents = decode_qr_codes(&bytes).expect("decode failed");
+        assert_eq!(contents, vec!["https://example.com/import".to_string()]);
+    }
+
+    #[test]
+    fn test_decode_qr_codes_rejects_blank_image() {
+        let blank = image::RgbaImage::from_pixel(64, 64, image::Rgba([255, 255, 255, 255]));
+        let mut bytes = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut bytes)
+            .write_image(blank.as_raw(), 64, 64, image::ExtendedColorType::Rgba8)
+            .unwrap();
+        assert!(decode_qr_codes(&bytes).is_err());
+        assert!(decode_qr_codes(b"not an image").is_err());
+    }
+
+    #[test]
+    fn test_decode_qr_codes_rejects_oversized_image() {
+        let side = 4097;
+        let blank = image::GrayImage::from_pixel(side, side, image::Luma([255]));
+        let mut bytes = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut bytes)
+            .write_image(blank.as_raw(), side, side, image::ExtendedColorType::L8)
+            .unwrap();
+        assert!(bytes.len() <= MAX_IMPORT_BYTES);
+        let err = decode_qr_codes(&bytes).unwrap_err();
+        assert!(err.contains("trop grande"), "{err}");
+    }
+
+    #[test]
+    fn test_verify_scannable() {
+        let image = render_qr_image("https://example.com/scan", &options(256, false, true))
+            .expect("render failed");
+        assert!(verify_scannable("https://example.com/scan", &image));
+        assert!(!verify_scannable("https://example.com/other", &image));
+
+        let blank = image::RgbaImage::from_pixel(64, 64, image::Rgba([255, 255, 255, 255]));
+        assert!(!verify_scannable("https://example.com/scan", &blank));
+    }
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        assert_eq!(fnv1a(*b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(*b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(*b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_check_scannable() {
+        let text = "https://example.com/scan";
+        assert!(check_scannable(text, &options(128, false, true), false).is_ok());
+        let inverted = RenderOptions {
+            size: 128,
+            foreground: QrColor::WHITE,
+            background: QrColor::BLACK,
+            ..RenderOptions::default()
+        };
+        assert!(check_scannable(text, &inverted, false).is_err());
+        assert!(check_scannable(text, &inverted, true).is_ok());
+        let refused = RenderOptions {
+            foreground: QrColor([230, 230, 230, 255]),
+            background: QrColor::WHITE,
+            ..inverted
+        };
+        assert!(check_scannable(text, &refused, true).is_err());
+    }
+
     // bench test removed
 }
@@ -0,0 +1,688 @@
+//! API REST publique (`/api/v1`)
+//!
+//! Reprend les fonctions serveur de l'interface pour les scripts et la CI :
+//! CRUD des QR codes sauvegardés et rendu d'un aperçu. Les erreurs ont toutes
+//! la forme `{"error": {"code": ..., "message": ...}}` ; la description
+//! OpenAPI est produite par [`openapi_document`] à partir de la configuration.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::constants::APP_NAME;
+use crate::config::UiSettings;
+use crate::models::qr_code::{
+    QrId, RenderOptions, RenderedQr, SavedList, SavedQr, UIQr, SCHEMA_VERSION,
+};
+use crate::services::analytics::now;
+use crate::services::dynamic_link::check_dynamic;
+use crate::services::migration::migrate;
+use crate::services::qr_code::{check_logo, check_scannable, render_preview};
+use crate::services::storage::{QrStorage, SaveError};
+use crate::services::thumbnail::thumbnails;
+
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Erreur de l'API, avec son statut HTTP
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiError {
+    pub status: u16,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: 400,
+            code: "invalid_request",
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            status: 404,
+            code: "not_found",
+            message: message.into(),
+        }
+    }
+
+    pub fn method_not_allowed() -> Self {
+        Self {
+            status: 405,
+            code: "method_not_allowed",
+            message: "Méthode non prise en charge pour cette ressource".into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self {
+            status: 409,
+            code: "conflict",
+            message: message.into(),
+        }
+    }
+
+    /// Le détail est journalisé mais pas renvoyé au client.
+    pub fn internal(error: impl std::fmt::Display) -> Self {
+        eprintln!("Erreur de l'API: {}", error);
+        Self {
+            status: 500,
+            code: "internal",
+            message: "Erreur du serveur".into(),
+        }
+    }
+
+    pub fn body(&self) -> Value {
+        json!({ "error": { "code": self.code, "message": self.message } })
+    }
+}
+
+/// Corps de `POST /api/v1/render`
+#[derive(Clone, Debug, Deserialize)]
+pub struct RenderRequest {
+    pub text: String,
+    /// Options complètes ; par défaut celles d'un nouveau QR code
+    #[serde(default)]
+    pub options: Option<RenderOptions>,
+}
+
+/// Paramètres de `POST /api/v1/codes` et `PUT /api/v1/codes/{id}`
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SaveQuery {
+    /// Enregistre même si le rendu ne se relit pas
+    #[serde(default)]
+    pub force: bool,
+}
+
+fn parse_id(id: &str) -> Result<QrId, ApiError> {
+    QrId::parse(id).map_err(ApiError::bad_request)
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, ApiError> {
+    serde_json::from_slice(body)
+        .map_err(|e| ApiError::bad_request(format!("JSON invalide : {}", e)))
+}
+
+/// Lit un QR code envoyé par un client, migré au format courant comme un
+/// enregistrement stocké.
+fn parse_code(value: Value) -> Result<SavedQr, ApiError> {
+    let (value, _) = migrate(value).map_err(ApiError::bad_request)?;
+    serde_json::from_value(value).map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Valide puis enregistre un QR code ; renvoie la version stockée.
+fn store(
+    storage: &dyn QrStorage,
+    ui: &UiSettings,
+    qr: SavedQr,
+    query: &SaveQuery,
+) -> Result<SavedQr, ApiError> {
+    ui.check_request(&qr.text, qr.size)
+        .map_err(ApiError::bad_request)?;
+    check_dynamic(&qr, &ui.public_url).map_err(ApiError::bad_request)?;
+    if let Some(logo) = &qr.logo {
+        check_logo(logo).map_err(ApiError::bad_request)?;
+    }
+    check_scannable(&qr.text, &qr.render_options(), query.force).map_err(ApiError::bad_request)?;
+    let revision = storage.save(&qr).map_err(|e| match e {
+        SaveError::Conflict(message) => ApiError::conflict(message),
+        SaveError::Other(message) => ApiError::internal(message),
+    })?;
+    thumbnails().invalidate(&qr.id);
+    Ok(SavedQr { revision, ..qr })
+}
+
+/// `GET /api/v1/codes`
+pub fn list_codes(storage: &dyn QrStorage) -> Result<SavedList, ApiError> {
+    storage.list().map_err(ApiError::internal)
+}
+
+/// `GET /api/v1/codes/{id}`
+pub fn get_code(storage: &dyn QrStorage, id: &str) -> Result<SavedQr, ApiError> {
+    let id = parse_id(id)?;
+    storage
+        .find(&id)
+        .map_err(ApiError::internal)?
+        .ok_or_else(|| ApiError::not_found(format!("Le QR code {} n'existe pas", id)))
+}
+
+/// `POST /api/v1/codes` : seuls `text` et les options à changer sont
+/// requis ; l'identifiant est généré s'il est absent.
+pub fn create_code(
+    storage: &dyn QrStorage,
+    ui: &UiSettings,
+    body: &[u8],
+    query: &SaveQuery,
+) -> Result<SavedQr, ApiError> {
+    let Value::Object(mut fields) = parse_json(body)? else {
+        return Err(ApiError::bad_request("Le corps doit être un objet JSON"));
+    };
+    let defaults = [
+        ("id", json!(QrId::generate())),
+        ("size", json!(ui.default_size)),
+        ("transparent", json!(false)),
+        ("margin", json!(ui.default_margin)),
+        ("created_at", json!(now().to_string())),
+        ("schema_version", json!(SCHEMA_VERSION)),
+    ];
+    for (key, value) in defaults {
+        fields.entry(key).or_insert(value);
+    }
+    // Une création ne peut pas partir d'une révision existante
+    fields.insert("revision".into(), json!(0));
+    store(storage, ui, parse_code(Value::Object(fields))?, query)
+}
+
+/// `PUT /api/v1/codes/{id}` : remplace le QR code à partir de la révision
+/// indiquée dans le corps, comme `save_qr`.
+pub fn update_code(
+    storage: &dyn QrStorage,
+    ui: &UiSettings,
+    id: &str,
+    body: &[u8],
+    query: &SaveQuery,
+) -> Result<SavedQr, ApiError> {
+    let id = parse_id(id)?;
+    let qr = parse_code(parse_json(body)?)?;
+    if qr.id != id {
+        return Err(ApiError::bad_request(format!(
+            "L'identifiant du corps ({}) ne correspond pas à l'URL ({})",
+            qr.id, id
+        )));
+    }
+    store(storage, ui, qr, query)
+}
+
+/// `DELETE /api/v1/codes/{id}`
+pub fn delete_code(storage: &dyn QrStorage, id: &str) -> Result<(), ApiError> {
+    let qr = get_code(storage, id)?;
+    storage.delete(&qr.id).map_err(ApiError::internal)?;
+    thumbnails().invalidate(&qr.id);
+    Ok(())
+}
+
+/// `POST /api/v1/render`
+pub fn render(ui: &UiSettings, body: &[u8]) -> Result<RenderedQr, ApiError> {
+    let request: RenderRequest = parse_json(body)?;
+    if request.text.is_empty() {
+        return Err(ApiError::bad_request("Le champ text est requis"));
+    }
+    let options = match request.options {
+        Some(options) => options,
+        None => UIQr::new(request.text.clone(), ui).render_options(),
+    };
+    ui.check_request(&request.text, options.size)
+        .map_err(ApiError::bad_request)?;
+    render_preview(&request.text, &options).map_err(ApiError::bad_request)
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } }
+    })
+}
+
+fn json_response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } }
+    })
+}
+
+fn json_body(schema: &str) -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", schema) } } }
+    })
+}
+
+/// Description OpenAPI 3 de l'API ; les limites reprennent la configuration.
+pub fn openapi_document(ui: &UiSettings) -> Value {
+    let id_parameter = json!({
+        "name": "id",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string", "pattern": "^qr-[0-9]+$" }
+    });
+    let force_parameter = json!({
+        "name": "force",
+        "in": "query",
+        "description": "Enregistre même si le rendu ne se relit pas",
+        "schema": { "type": "boolean", "default": false }
+    });
+    let text = json!({ "type": "string", "minLength": 1, "maxLength": ui.max_payload_length });
+    let size = json!({ "type": "integer", "enum": ui.allowed_sizes });
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": format!("{} API", APP_NAME),
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Création, modification et rendu de QR codes."
+        },
+        "paths": {
+            format!("{}/codes", API_PREFIX): {
+                "get": {
+                    "summary": "Liste les QR codes sauvegardés",
+                    "operationId": "listCodes",
+                    "responses": { "200": json_response("QR codes lisibles et enregistrements illisibles", "SavedList") }
+                },
+                "post": {
+                    "summary": "Crée un QR code",
+                    "description": "Seul `text` est requis ; `id`, `size`, `margin`, `transparent` et `created_at` prennent des valeurs par défaut.",
+                    "operationId": "createCode",
+                    "parameters": [force_parameter.clone()],
+                    "requestBody": json_body("NewCode"),
+                    "responses": {
+                        "201": json_response("QR code créé", "SavedQr"),
+                        "400": error_response("Requête invalide ou rendu illisible sans `force`"),
+                        "409": error_response("Identifiant ou lien court déjà utilisé")
+                    }
+                }
+            },
+            format!("{}/codes/{{id}}", API_PREFIX): {
+                "parameters": [id_parameter],
+                "get": {
+                    "summary": "Charge un QR code",
+                    "operationId": "getCode",
+                    "responses": {
+                        "200": json_response("QR code", "SavedQr"),
+                        "400": error_response("Identifiant invalide"),
+                        "404": error_response("QR code inconnu")
+                    }
+                },
+                "put": {
+                    "summary": "Enregistre un QR code",
+                    "description": "`revision` doit être celle stockée (0 pour créer) ; la réponse porte la nouvelle révision.",
+                    "operationId": "updateCode",
+                    "parameters": [force_parameter],
+                    "requestBody": json_body("SavedQr"),
+                    "responses": {
+                        "200": json_response("QR code enregistré", "SavedQr"),
+                        "400": error_response("Requête invalide ou rendu illisible sans `force`"),
+                        "409": error_response("Modifié entre-temps ou lien court déjà utilisé")
+                    }
+                },
+                "delete": {
+                    "summary": "Supprime un QR code et ses statistiques",
+                    "operationId": "deleteCode",
+                    "responses": {
+                        "204": { "description": "QR code supprimé" },
+                        "400": error_response("Identifiant invalide"),
+                        "404": error_response("QR code inconnu")
+                    }
+                }
+            },
+            format!("{}/render", API_PREFIX): {
+                "post": {
+                    "summary": "Génère un aperçu PNG",
+                    "operationId": "render",
+                    "requestBody": json_body("RenderRequest"),
+                    "responses": {
+                        "200": json_response("Aperçu", "RenderedQr"),
+                        "400": error_response("Requête invalide")
+                    }
+                }
+            },
+            format!("{}/openapi.json", API_PREFIX): {
+                "get": {
+                    "summary": "Ce document",
+                    "operationId": "openapi",
+                    "responses": { "200": { "description": "Description OpenAPI 3", "content": { "application/json": {} } } }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Error": {
+                    "type": "object",
+                    "required": ["error"],
+                    "properties": {
+                        "error": {
+                            "type": "object",
+                            "required": ["code", "message"],
+                            "properties": {
+                                "code": { "type": "string", "enum": ["invalid_request", "not_found", "method_not_allowed", "conflict", "internal"] },
+                                "message": { "type": "string" }
+                            }
+                        }
+                    }
+                },
+                "DynamicLink": {
+                    "type": "object",
+                    "required": ["slug", "target"],
+                    "properties": {
+                        "slug": { "type": "string", "pattern": "^[A-Za-z0-9_-]{1,64}$" },
+                        "target": { "type": "string", "format": "uri" }
+                    }
+                },
+                "NewCode": {
+                    "type": "object",
+                    "required": ["text"],
+                    "additionalProperties": true,
+                    "properties": {
+                        "id": { "type": "string" },
+                        "text": text,
+                        "size": size,
+                        "transparent": { "type": "boolean" },
+                        "margin": { "type": "boolean" },
+                        "dynamic": { "$ref": "#/components/schemas/DynamicLink" }
+                    }
+                },
+                "SavedQr": {
+                    "type": "object",
+                    "required": ["id", "text", "size", "transparent", "margin", "created_at"],
+                    "additionalProperties": true,
+                    "description": "Les options de rendu (couleurs, logo, styles...) suivent le format de `RenderOptions`.",
+                    "properties": {
+                        "schema_version": { "type": "integer" },
+                        "id": { "type": "string" },
+                        "text": text,
+                        "size": size,
+                        "transparent": { "type": "boolean" },
+                        "margin": { "type": "boolean" },
+                        "revision": { "type": "integer", "minimum": 0 },
+                        "dynamic": { "$ref": "#/components/schemas/DynamicLink" },
+                        "created_at": { "type": "string", "description": "Secondes depuis l'époque Unix" }
+                    }
+                },
+                "SavedList": {
+                    "type": "object",
+                    "properties": {
+                        "codes": { "type": "array", "items": { "$ref": "#/components/schemas/SavedQr" } },
+                        "unreadable": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": { "source": { "type": "string" }, "error": { "type": "string" } }
+                            }
+                        }
+                    }
+                },
+                "RenderOptions": {
+                    "type": "object",
+                    "additionalProperties": true,
+                    "properties": {
+                        "size": size,
+                        "transparent": { "type": "boolean" },
+                        "margin": { "type": "boolean" },
+                        "error_correction": { "type": "string", "enum": ["L", "M", "Q", "H"] },
+                        "foreground": { "type": "string", "pattern": "^#[0-9A-Fa-f]{8}$" },
+                        "background": { "type": "string", "pattern": "^#[0-9A-Fa-f]{8}$" }
+                    }
+                },
+                "RenderRequest": {
+                    "type": "object",
+                    "required": ["text"],
+                    "properties": {
+                        "text": text,
+                        "options": { "$ref": "#/components/schemas/RenderOptions" }
+                    }
+                },
+                "RenderedQr": {
+                    "type": "object",
+                    "properties": {
+                        "data_url": { "type": "string", "description": "Image PNG en data URL" },
+                        "scannable": { "type": "boolean" }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::JsonDirStorage;
+
+    fn with_storage(test: impl FnOnce(&dyn QrStorage)) {
+        let dir = std::env::temp_dir().join(format!("qr-craft-test-{}", fastrand::u64(..)));
+        test(&JsonDirStorage::new(&dir));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn body(value: Value) -> Vec<u8> {
+        serde_json::to_vec(&value).unwrap()
+    }
+
+    #[test]
+    fn test_crud() {
+        let ui = UiSettings::default();
+        with_storage(|storage| {
+            let created = create_code(
+                storage,
+                &ui,
+                &body(json!({ "text": "https://example.com" })),
+                &SaveQuery::default(),
+            )
+            .unwrap();
+            assert_eq!(created.revision, 1);
+            assert_eq!(created.size, ui.default_size);
+            assert_eq!(created.schema_version, SCHEMA_VERSION);
+
+            let id = created.id.to_string();
+            assert_eq!(get_code(storage, &id).unwrap(), created);
+            assert_eq!(list_codes(storage).unwrap().codes, vec![created.clone()]);
+
+            let mut edited = serde_json::to_value(&created).unwrap();
+            edited["text"] = json!("modifié");
+            let updated = update_code(
+                storage,
+                &ui,
+                &id,
+                &body(edited.clone()),
+                &SaveQuery::default(),
+            )
+            .unwrap();
+            assert_eq!((updated.revision, updated.text.as_str()), (2, "modifié"));
+
+            // Même corps une seconde fois : révision périmée
+            let error =
+                update_code(storage, &ui, &id, &body(edited), &SaveQuery::default()).unwrap_err();
+            assert_eq!((error.status, error.code), (409, "conflict"));
+
+            delete_code(storage, &id).unwrap();
+            assert_eq!(get_code(storage, &id).unwrap_err().status, 404);
+            assert_eq!(delete_code(storage, &id).unwrap_err().status, 404);
+        });
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let ui = UiSettings::default();
+        with_storage(|storage| {
+            let status = |result: Result<SavedQr, ApiError>| result.unwrap_err().status;
+            assert_eq!(
+                status(create_code(storage, &ui, b"{", &SaveQuery::default())),
+                400
+            );
+            assert_eq!(
+                status(create_code(storage, &ui, b"[]", &SaveQuery::default())),
+                400
+            );
+            assert_eq!(
+                status(create_code(
+                    storage,
+                    &ui,
+                    &body(json!({})),
+                    &SaveQuery::default()
+                )),
+                400
+            );
+            assert_eq!(
+                status(create_code(
+                    storage,
+                    &ui,
+                    &body(json!({ "text": "a", "size": 300 })),
+                    &SaveQuery::default()
+                )),
+                400
+            );
+            assert_eq!(
+                status(create_code(
+                    storage,
+                    &ui,
+                    &body(json!({ "text": "a", "id": "../x" })),
+                    &SaveQuery::default()
+                )),
+                400
+            );
+            assert_eq!(status(get_code(storage, "../x")), 400);
+            let logo = json!({ "data": "", "scale_percent": 20, "padding_percent": u32::MAX });
+            assert_eq!(
+                status(create_code(
+                    storage,
+                    &ui,
+                    &body(json!({ "text": "a", "logo": logo })),
+                    &SaveQuery::default()
+                )),
+                400
+            );
+
+            let created = create_code(
+                storage,
+                &ui,
+                &body(json!({ "text": "a" })),
+                &SaveQuery::default(),
+            )
+            .unwrap();
+            let other = update_code(
+                storage,
+                &ui,
+                "qr-1",
+                &body(serde_json::to_value(&created).unwrap()),
+                &SaveQuery::default(),
+            );
+            assert_eq!(status(other), 400);
+
+            // Même identifiant à la création : conflit
+            let again = create_code(
+                storage,
+                &ui,
+                &body(json!({ "text": "b", "id": created.id })),
+                &SaveQuery::default(),
+            );
+            assert_eq!(status(again), 409);
+        });
+    }
+
+    #[test]
+    fn test_dynamic_slug_conflict() {
+        let ui = UiSettings::default();
+        with_storage(|storage| {
+            let code = |target: &str| {
+                body(json!({
+                    "text": "https://qr.example.com/r/promo",
+                    "dynamic": { "slug": "promo", "target": target }
+                }))
+            };
+            create_code(
+                storage,
+                &ui,
+                &code("https://example.com"),
+                &SaveQuery::default(),
+            )
+            .unwrap();
+            let error = create_code(
+                storage,
+                &ui,
+                &code("https://example.org"),
+                &SaveQuery::default(),
+            )
+            .unwrap_err();
+            assert_eq!(error.status, 409);
+            assert_eq!(
+                create_code(
+                    storage,
+                    &ui,
+                    &code("javascript:alert(1)"),
+                    &SaveQuery::default()
+                )
+                .unwrap_err()
+                .status,
+                400
+            );
+        });
+    }
+
+    #[test]
+    fn test_unscannable_needs_force() {
+        let ui = UiSettings::default();
+        with_storage(|storage| {
+            let inverted = body(json!({
+                "text": "https://example.com",
+                "foreground": "#FFFFFFFF",
+                "background": "#000000FF"
+            }));
+            let error = create_code(storage, &ui, &inverted, &SaveQuery::default()).unwrap_err();
+            assert_eq!(error.status, 400);
+            assert!(list_codes(storage).unwrap().codes.is_empty());
+            let forced = SaveQuery { force: true };
+            assert_eq!(
+                create_code(storage, &ui, &inverted, &forced)
+                    .unwrap()
+                    .revision,
+                1
+            );
+        });
+    }
+
+    #[test]
+    fn test_render() {
+        let ui = UiSettings::default();
+        let rendered = render(&ui, &body(json!({ "text": "https://example.com" }))).unwrap();
+        assert!(rendered.data_url.starts_with("data:image/png;base64,"));
+        assert_eq!(
+            render(&ui, &body(json!({ "text": "" })))
+                .unwrap_err()
+                .status,
+            400
+        );
+        assert_eq!(render(&ui, b"texte").unwrap_err().status, 400);
+    }
+
+    #[test]
+    fn test_error_body() {
+        let error = ApiError::not_found("absent");
+        assert_eq!(
+            error.body(),
+            json!({ "error": { "code": "not_found", "message": "absent" } })
+        );
+        assert_eq!(ApiError::internal("détail").message, "Erreur du serveur");
+    }
+
+    #[test]
+    fn test_openapi_document() {
+        let ui = UiSettings::default();
+        let document = openapi_document(&ui);
+        assert_eq!(document["openapi"], "3.0.3");
+        let paths = document["paths"].as_object().unwrap();
+        for (path, methods) in [
+            ("/api/v1/codes", &["get", "post"][..]),
+            ("/api/v1/codes/{id}", &["get", "put", "delete"][..]),
+            ("/api/v1/render", &["post"][..]),
+            ("/api/v1/openapi.json", &["get"][..]),
+        ] {
+            for method in methods {
+                assert!(paths[path].get(*method).is_some(), "{} {}", method, path);
+            }
+        }
+        assert_eq!(
+            document["components"]["schemas"]["SavedQr"]["properties"]["size"]["enum"],
+            json!(ui.allowed_sizes)
+        );
+
+        // Toutes les références pointent vers un schéma défini
+        let text = document.to_string();
+        for reference in text.split("#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(
+                document["components"]["schemas"].get(name).is_some(),
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,1480 @@
+//! Persistance des QR codes sauvegardés
+//!
+//! Les fonctions serveur passent par le trait [`QrStorage`], dont
+//! l'implémentation est choisie au démarrage : un fichier JSON par QR code
+//! dans un répertoire, une base SQLite embarquée (fonctionnalité `sqlite`),
+//! ou le `localStorage` du navigateur en mode hors ligne (fonctionnalité
+//! `offline`, sans serveur).
+//! Les enregistrements sont migrés à la lecture (voir [`crate::services::migration`]).
+//!
+//! Chaque enregistrement porte une révision : une sauvegarde n'aboutit que si
+//! elle part de la révision stockée, ce qui détecte les éditions concurrentes.
+//! La sauvegarde vérifie aussi que le slug d'un QR code dynamique n'est pas
+//! déjà utilisé par un autre.
+//!
+//! Le stockage conserve aussi les scans des QR codes dynamiques (voir
+//! [`crate::services::analytics`]), supprimés avec leur QR code.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{settings, Settings};
+use crate::models::analytics::ScanEvent;
+use crate::models::qr_code::{QrId, SavedList, SavedQr, UnreadableRecord};
+use crate::services::dynamic_link::slug_in_use;
+use crate::services::migration::parse_record;
+
+/// Stockage des QR codes sauvegardés.
+pub trait QrStorage: Send + Sync {
+    /// Crée ou remplace un QR code si sa révision est celle stockée (0 pour
+    /// un nouveau QR code) et si son slug est libre ; renvoie la nouvelle
+    /// révision.
+    fn save(&self, qr: &SavedQr) -> Result<u64, SaveError>;
+
+    /// Réécrit un QR code sans changer sa révision, sauf s'il a été modifié
+    /// entre-temps ; indique si l'écriture a eu lieu.
+    fn replace(&self, qr: &SavedQr) -> Result<bool, String>;
+
+    /// Renvoie tous les QR codes lisibles et le rapport des autres.
+    fn list(&self) -> Result<SavedList, String>;
+
+    /// Cherche un QR code par son identifiant.
+    fn find(&self, id: &QrId) -> Result<Option<SavedQr>, String>;
+
+    /// Charge un QR code par son identifiant ; échoue s'il n'existe pas.
+    fn load(&self, id: &QrId) -> Result<SavedQr, String> {
+        self.find(id)?
+            .ok_or_else(|| format!("Le QR code {} n'existe pas", id))
+    }
+
+    /// Supprime un QR code et ses scans ; échoue s'il n'existe pas.
+    fn delete(&self, id: &QrId) -> Result<(), String>;
+
+    /// Enregistre un passage par la redirection d'un QR code dynamique.
+    fn record_scan(&self, scan: &ScanEvent) -> Result<(), String>;
+
+    /// Scans d'un QR code, du plus ancien au plus récent.
+    fn scans(&self, id: &QrId) -> Result<Vec<ScanEvent>, String>;
+
+    /// Cherche le QR code dynamique utilisant ce slug.
+    fn find_by_slug(&self, slug: &str) -> Result<Option<SavedQr>, String>;
+}
+
+/// Backends de stockage disponibles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    #[default]
+    Json,
+    Sqlite,
+    /// `localStorage` du navigateur, choisi par l'application hors ligne
+    Browser,
+}
+
+impl StorageKind {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "sqlite" => Some(Self::Sqlite),
+            "browser" => Some(Self::Browser),
+            _ => None,
+        }
+    }
+}
+
+/// Ouvre le backend configuré dans le répertoire de données.
+pub fn open_storage(settings: &Settings) -> Result<Box<dyn QrStorage>, String> {
+    let dir = &settings.data_dir;
+    let rewrite = settings.rewrite_migrated;
+    match settings.storage {
+        StorageKind::Json => Ok(Box::new(JsonDirStorage::new(dir).rewrite_migrated(rewrite))),
+        #[cfg(feature = "sqlite")]
+        StorageKind::Sqlite => Ok(Box::new(
+            SqliteStorage::open(&dir.join("qr-craft.db"))?.rewrite_migrated(rewrite),
+        )),
+        #[cfg(not(feature = "sqlite"))]
+        StorageKind::Sqlite => {
+            Err("Le stockage SQLite nécessite la fonctionnalité `sqlite`".into())
+        }
+        #[cfg(feature = "offline")]
+        StorageKind::Browser => Ok(Box::new(
+            KeyValueStorage::new(BrowserStore).rewrite_migrated(rewrite),
+        )),
+        #[cfg(not(feature = "offline"))]
+        StorageKind::Browser => {
+            Err("Le stockage du navigateur nécessite la fonctionnalité `offline`".into())
+        }
+    }
+}
+
+/// Ouvre en lecture seule le stockage existant du répertoire de données :
+/// rien n'est créé et les enregistrements migrés ne sont pas réécrits.
+pub fn open_existing_storage(settings: &Settings) -> Result<Box<dyn QrStorage>, String> {
+    let dir = &settings.data_dir;
+    match settings.storage {
+        StorageKind::Json => {
+            if !dir.is_dir() {
+                return Err(format!("Le répertoire de données {:?} n'existe pas", dir));
+            }
+            Ok(Box::new(JsonDirStorage::new(dir).rewrite_migrated(false)))
+        }
+        #[cfg(feature = "sqlite")]
+        StorageKind::Sqlite => Ok(Box::new(SqliteStorage::open_read_only(
+            &dir.join("qr-craft.db"),
+        )?)),
+        _ => open_storage(&Settings {
+            rewrite_migrated: false,
+            ..settings.clone()
+        }),
+    }
+}
+
+/// Indique si `qr` est un QR code dynamique utilisant ce slug.
+fn uses_slug(qr: &SavedQr, slug: &str) -> bool {
+    qr.dynamic.as_ref().is_some_and(|link| link.slug == slug)
+}
+
+/// Réécrit un enregistrement migré ; un échec n'empêche pas la lecture.
+fn rewrite_migrated(storage: &dyn QrStorage, qr: &SavedQr) {
+    if let Err(e) = storage.replace(qr) {
+        eprintln!("Erreur de migration du QR code {}: {}", qr.id, e);
+    }
+}
+
+/// Échec d'une sauvegarde
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SaveError {
+    /// La sauvegarde ne part pas de la révision stockée, ou le slug est déjà
+    /// utilisé par un autre QR code
+    Conflict(String),
+    /// Erreur de lecture ou d'écriture du stockage
+    Other(String),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Conflict(message) | SaveError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for SaveError {
+    fn from(message: String) -> Self {
+        SaveError::Other(message)
+    }
+}
+
+/// Refuse une sauvegarde qui ne part pas de la révision stockée.
+fn check_revision(id: &QrId, stored: u64, expected: u64) -> Result<(), SaveError> {
+    if stored != expected {
+        return Err(SaveError::Conflict(format!(
+            "Le QR code {} a été modifié entre-temps (révision {} enregistrée, {} attendue) : \
+             rechargez-le avant d'enregistrer",
+            id, stored, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Écrit un fichier de façon atomique : le contenu est écrit et synchronisé
+/// dans un fichier temporaire du même répertoire, puis renommé.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = dir.join(format!(".{}.{}.tmp", name, fastrand::u64(..)));
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+/// Verrous d'écriture par identifiant : les sauvegardes d'un même QR code
+/// sont sérialisées, celles de QR codes différents restent parallèles.
+#[derive(Default)]
+pub struct WriterLocks {
+    locks: Mutex<HashMap<QrId, Arc<Mutex<()>>>>,
+}
+
+impl WriterLocks {
+    fn locks(&self) -> std::sync::MutexGuard<'_, HashMap<QrId, Arc<Mutex<()>>>> {
+        self.locks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Exécute `f` en détenant le verrou d'écriture de `id`.
+    pub fn with<T>(&self, id: &QrId, f: impl FnOnce() -> T) -> T {
+        let lock = self.locks().entry(id.clone()).or_default().clone();
+        let result = {
+            let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+            f()
+        };
+        // Libérer l'entrée si personne d'autre n'attend ce verrou
+        let mut locks = self.locks();
+        if Arc::strong_count(&lock) == 2 {
+            locks.remove(id);
+        }
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.locks().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+type Slugs = HashMap<String, QrId>;
+
+/// Ajoute à l'index le slug d'un QR code dynamique.
+fn index_slug(slugs: &mut Slugs, qr: &SavedQr) {
+    if let Some(link) = &qr.dynamic {
+        slugs.insert(link.slug.clone(), qr.id.clone());
+    }
+}
+
+/// Index slug → identifiant en mémoire, pour les stockages qui ne savent pas
+/// chercher un slug sans tout relire. Construit au premier besoin, il est mis
+/// à jour à chaque sauvegarde et suppression.
+#[derive(Default)]
+pub struct SlugIndex {
+    slugs: Mutex<Option<Slugs>>,
+}
+
+impl SlugIndex {
+    fn slugs(&self) -> std::sync::MutexGuard<'_, Option<Slugs>> {
+        self.slugs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Identifiant du QR code utilisant `slug` ; `build` relit les slugs de
+    /// tous les QR codes si l'index n'est pas encore construit.
+    pub fn find(
+        &self,
+        slug: &str,
+        build: impl FnOnce() -> Result<Slugs, String>,
+    ) -> Result<Option<QrId>, String> {
+        let mut guard = self.slugs();
+        let slugs = match &mut *guard {
+            Some(slugs) => slugs,
+            empty => empty.insert(build()?),
+        };
+        Ok(slugs.get(slug).cloned())
+    }
+
+    /// Sauvegarde `qr` par `write` après avoir vérifié que son slug est libre.
+    /// Le verrou de l'index est détenu jusqu'à la fin de l'écriture : deux
+    /// QR codes ne peuvent pas obtenir le même slug.
+    pub fn save(
+        &self,
+        qr: &SavedQr,
+        build: impl FnOnce() -> Result<Slugs, String>,
+        write: impl FnOnce() -> Result<u64, SaveError>,
+    ) -> Result<u64, SaveError> {
+        let Some(link) = &qr.dynamic else {
+            let revision = write()?;
+            self.forget(&qr.id);
+            return Ok(revision);
+        };
+        let mut guard = self.slugs();
+        let slugs = match &mut *guard {
+            Some(slugs) => slugs,
+            empty => empty.insert(build()?),
+        };
+        if slugs.get(&link.slug).is_some_and(|owner| *owner != qr.id) {
+            return Err(SaveError::Conflict(slug_in_use(link)));
+        }
+        let revision = write()?;
+        slugs.retain(|_, owner| *owner != qr.id);
+        index_slug(slugs, qr);
+        Ok(revision)
+    }
+
+    /// Retire de l'index le slug d'un QR code.
+    pub fn forget(&self, id: &QrId) {
+        if let Some(slugs) = self.slugs().as_mut() {
+            slugs.retain(|_, owner| owner != id);
+        }
+    }
+}
+
+static STORAGE: OnceLock<Box<dyn QrStorage>> = OnceLock::new();
+
+/// Stockage partagé par les fonctions serveur, ouvert au premier appel.
+pub fn storage() -> Result<&'static dyn QrStorage, String> {
+    if let Some(storage) = STORAGE.get() {
+        return Ok(storage.as_ref());
+    }
+    let storage = open_storage(settings()?)?;
+    Ok(STORAGE.get_or_init(|| storage).as_ref())
+}
+
+/// Un fichier `<id>.json` par QR code dans un répertoire, et ses scans
+/// dans `scans/<id>.jsonl` (un événement JSON par ligne, en ajout seul).
+pub struct JsonDirStorage {
+    dir: PathBuf,
+    rewrite: bool,
+    locks: WriterLocks,
+    slugs: SlugIndex,
+}
+
+impl JsonDirStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            rewrite: true,
+            locks: WriterLocks::default(),
+            slugs: SlugIndex::default(),
+        }
+    }
+
+    /// Réécrit (ou non) au format courant les fichiers migrés à la lecture.
+    pub fn rewrite_migrated(mut self, rewrite: bool) -> Self {
+        self.rewrite = rewrite;
+        self
+    }
+
+    /// Chemin du fichier JSON d'un QR code ; l'identifiant validé ne peut
+    /// pas sortir du répertoire.
+    pub fn path(&self, id: &QrId) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn scans_path(&self, id: &QrId) -> PathBuf {
+        self.dir.join("scans").join(format!("{}.jsonl", id))
+    }
+
+    /// Révision stockée (0 si le fichier n'existe pas).
+    fn stored_revision(&self, id: &QrId) -> Result<u64, String> {
+        match fs::read_to_string(self.path(id)) {
+            Ok(s) => Ok(parse_record(&s)?.0.revision),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn write(&self, qr: &SavedQr) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(qr).map_err(|e| e.to_string())?;
+        write_atomic(&self.path(&qr.id), json.as_bytes())
+    }
+
+    /// Slugs de tous les QR codes dynamiques, pour construire l'index ; les
+    /// fichiers illisibles sont ignorés et les fichiers migrés pas réécrits.
+    fn read_slugs(&self) -> Result<Slugs, String> {
+        let mut slugs = Slugs::new();
+        if !self.dir.exists() {
+            return Ok(slugs);
+        }
+        for entry in fs::read_dir(&self.dir).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let record = fs::read_to_string(&path).map_err(|e| e.to_string());
+                if let Ok((qr, _)) = record.and_then(|s| parse_record(&s)) {
+                    index_slug(&mut slugs, &qr);
+                }
+            }
+        }
+        Ok(slugs)
+    }
+}
+
+impl QrStorage for JsonDirStorage {
+    fn save(&self, qr: &SavedQr) -> Result<u64, SaveError> {
+        self.locks.with(&qr.id, || {
+            self.slugs.save(
+                qr,
+                || self.read_slugs(),
+                || {
+                    let stored = self.stored_revision(&qr.id)?;
+                    check_revision(&qr.id, stored, qr.revision)?;
+                    let next = SavedQr {
+                        revision: stored + 1,
+                        ..qr.clone()
+                    };
+                    self.write(&next)?;
+                    Ok(next.revision)
+                },
+            )
+        })
+    }
+
+    fn replace(&self, qr: &SavedQr) -> Result<bool, String> {
+        self.locks.with(&qr.id, || {
+            if self.stored_revision(&qr.id)? != qr.revision {
+                return Ok(false);
+            }
+            self.write(qr)?;
+            Ok(true)
+        })
+    }
+
+    fn list(&self) -> Result<SavedList, String> {
+        if !self.dir.exists() {
+            return Ok(SavedList::default());
+        }
+        let mut res = SavedList::default();
+        for entry in fs::read_dir(&self.dir).map_err(|e| e.to_string())? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let source = entry.file_name().to_string_lossy().into_owned();
+                match fs::read_to_string(&path).map_err(|e| e.to_string()) {
+                    Ok(s) => match parse_record(&s) {
+                        Ok((qr, migrated)) => {
+                            if migrated && self.rewrite {
+                                rewrite_migrated(self, &qr);
+                            }
+                            res.codes.push(qr);
+                        }
+                        Err(error) => {
+                            // Signaler le fichier mais continuer avec les autres
+                            eprintln!("Erreur de migration du fichier {:?}: {}", path, error);
+                            res.unreadable.push(UnreadableRecord { source, error });
+                        }
+                    },
+                    Err(error) => {
+                        eprintln!("Erreur de lecture du fichier {:?}: {}", path, error);
+                        res.unreadable.push(UnreadableRecord { source, error });
+                    }
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn find(&self, id: &QrId) -> Result<Option<SavedQr>, String> {
+        let s = match fs::read_to_string(self.path(id)) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        let (qr, migrated) = parse_record(&s)?;
+        if migrated && self.rewrite {
+            rewrite_migrated(self, &qr);
+        }
+        Ok(Some(qr))
+    }
+
+    fn delete(&self, id: &QrId) -> Result<(), String> {
+        self.locks.with(id, || {
+            let path = self.path(id);
+            if !path.exists() {
+                return Err(format!("Le fichier {} n'existe pas", id));
+            }
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+            self.slugs.forget(id);
+            match fs::remove_file(self.scans_path(id)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn record_scan(&self, scan: &ScanEvent) -> Result<(), String> {
+        let mut line = serde_json::to_string(scan).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let path = self.scans_path(&scan.qr_id);
+        self.locks.with(&scan.qr_id, || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(line.as_bytes()))
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn scans(&self, id: &QrId) -> Result<Vec<ScanEvent>, String> {
+        let s = match fs::read_to_string(self.scans_path(id)) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut scans = Vec::new();
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            // Une ligne tronquée (arrêt pendant l'écriture) est ignorée
+            match serde_json::from_str(line) {
+                Ok(scan) => scans.push(scan),
+                Err(e) => eprintln!("Scan illisible pour {}: {}", id, e),
+            }
+        }
+        Ok(scans)
+    }
+
+    fn find_by_slug(&self, slug: &str) -> Result<Option<SavedQr>, String> {
+        let Some(id) = self.slugs.find(slug, || self.read_slugs())? else {
+            return Ok(None);
+        };
+        Ok(self.find(&id)?.filter(|qr| uses_slug(qr, slug)))
+    }
+}
+
+/// Magasin clé/valeur de chaînes, sur le modèle du `localStorage` du navigateur.
+pub trait KeyValueStore: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+    fn set(&self, key: &str, value: &str) -> Result<(), String>;
+    fn remove(&self, key: &str) -> Result<(), String>;
+    /// Toutes les clés du magasin, dans un ordre quelconque.
+    fn keys(&self) -> Result<Vec<String>, String>;
+}
+
+const KV_RECORD_PREFIX: &str = "qr-craft/qr/";
+const KV_SCANS_PREFIX: &str = "qr-craft/scans/";
+
+/// Stockage dans un magasin clé/valeur, pour le mode hors ligne : le JSON de
+/// chaque QR code sous `qr-craft/qr/<id>` et ses scans, sous forme de tableau
+/// JSON, sous `qr-craft/scans/<id>`.
+pub struct KeyValueStorage<S> {
+    store: S,
+    rewrite: bool,
+    locks: WriterLocks,
+    slugs: SlugIndex,
+}
+
+impl<S: KeyValueStore> KeyValueStorage<S> {
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            rewrite: true,
+            locks: WriterLocks::default(),
+            slugs: SlugIndex::default(),
+        }
+    }
+
+    /// Réécrit (ou non) au format courant les enregistrements migrés à la lecture.
+    pub fn rewrite_migrated(mut self, rewrite: bool) -> Self {
+        self.rewrite = rewrite;
+        self
+    }
+
+    fn record_key(id: &QrId) -> String {
+        format!("{}{}", KV_RECORD_PREFIX, id)
+    }
+
+    fn scans_key(id: &QrId) -> String {
+        format!("{}{}", KV_SCANS_PREFIX, id)
+    }
+
+    /// Révision stockée (0 si l'enregistrement n'existe pas).
+    fn stored_revision(&self, id: &QrId) -> Result<u64, String> {
+        match self.store.get(&Self::record_key(id))? {
+            Some(s) => Ok(parse_record(&s)?.0.revision),
+            None => Ok(0),
+        }
+    }
+
+    fn write(&self, qr: &SavedQr) -> Result<(), String> {
+        let json = serde_json::to_string(qr).map_err(|e| e.to_string())?;
+        self.store.set(&Self::record_key(&qr.id), &json)
+    }
+
+    /// Slugs de tous les QR codes dynamiques, pour construire l'index ; les
+    /// entrées illisibles sont ignorées et les entrées migrées pas réécrites.
+    fn read_slugs(&self) -> Result<Slugs, String> {
+        let mut slugs = Slugs::new();
+        for key in self.store.keys()? {
+            if !key.starts_with(KV_RECORD_PREFIX) {
+                continue;
+            }
+            if let Some(Ok((qr, _))) = self.store.get(&key)?.map(|s| parse_record(&s)) {
+                index_slug(&mut slugs, &qr);
+            }
+        }
+        Ok(slugs)
+    }
+}
+
+impl<S: KeyValueStore> QrStorage for KeyValueStorage<S> {
+    fn save(&self, qr: &SavedQr) -> Result<u64, SaveError> {
+        self.locks.with(&qr.id, || {
+            self.slugs.save(
+                qr,
+                || self.read_slugs(),
+                || {
+                    let stored = self.stored_revision(&qr.id)?;
+                    check_revision(&qr.id, stored, qr.revision)?;
+                    let next = SavedQr {
+                        revision: stored + 1,
+                        ..qr.clone()
+                    };
+                    self.write(&next)?;
+                    Ok(next.revision)
+                },
+            )
+        })
+    }
+
+    fn replace(&self, qr: &SavedQr) -> Result<bool, String> {
+        self.locks.with(&qr.id, || {
+            if self.stored_revision(&qr.id)? != qr.revision {
+                return Ok(false);
+            }
+            self.write(qr)?;
+            Ok(true)
+        })
+    }
+
+    fn list(&self) -> Result<SavedList, String> {
+        let mut res = SavedList::default();
+        for key in self.store.keys()? {
+            let Some(source) = key.strip_prefix(KV_RECORD_PREFIX) else {
+                continue;
+            };
+            let Some(s) = self.store.get(&key)? else {
+                continue;
+            };
+            match parse_record(&s) {
+                Ok((qr, migrated)) => {
+                    if migrated && self.rewrite {
+                        rewrite_migrated(self, &qr);
+                    }
+                    res.codes.push(qr);
+                }
+                Err(error) => {
+                    eprintln!("Erreur de migration de l'entrée {:?}: {}", key, error);
+                    res.unreadable.push(UnreadableRecord {
+                        source: source.to_string(),
+                        error,
+                    });
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn find(&self, id: &QrId) -> Result<Option<SavedQr>, String> {
+        let Some(s) = self.store.get(&Self::record_key(id))? else {
+            return Ok(None);
+        };
+        let (qr, migrated) = parse_record(&s)?;
+        if migrated && self.rewrite {
+            rewrite_migrated(self, &qr);
+        }
+        Ok(Some(qr))
+    }
+
+    fn delete(&self, id: &QrId) -> Result<(), String> {
+        self.locks.with(id, || {
+            let key = Self::record_key(id);
+            if self.store.get(&key)?.is_none() {
+                return Err(format!("Le QR code {} n'existe pas", id));
+            }
+            self.store.remove(&key)?;
+            self.slugs.forget(id);
+            self.store.remove(&Self::scans_key(id))
+        })
+    }
+
+    fn record_scan(&self, scan: &ScanEvent) -> Result<(), String> {
+        self.locks.with(&scan.qr_id, || {
+            let mut scans = self.scans(&scan.qr_id)?;
+            scans.push(scan.clone());
+            let json = serde_json::to_string(&scans).map_err(|e| e.to_string())?;
+            self.store.set(&Self::scans_key(&scan.qr_id), &json)
+        })
+    }
+
+    fn scans(&self, id: &QrId) -> Result<Vec<ScanEvent>, String> {
+        match self.store.get(&Self::scans_key(id))? {
+            Some(s) => serde_json::from_str(&s).map_err(|e| e.to_string()),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn find_by_slug(&self, slug: &str) -> Result<Option<SavedQr>, String> {
+        let Some(id) = self.slugs.find(slug, || self.read_slugs())? else {
+            return Ok(None);
+        };
+        Ok(self.find(&id)?.filter(|qr| uses_slug(qr, slug)))
+    }
+}
+
+/// `localStorage` du navigateur ; la fenêtre est retrouvée à chaque appel,
+/// le handle JavaScript ne pouvant pas être partagé entre threads.
+#[cfg(feature = "offline")]
+pub struct BrowserStore;
+
+#[cfg(feature = "offline")]
+impl BrowserStore {
+    fn local_storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| "Le stockage local du navigateur est indisponible".to_string())
+    }
+}
+
+#[cfg(feature = "offline")]
+fn js_error(e: web_sys::wasm_bindgen::JsValue) -> String {
+    e.as_string()
+        .unwrap_or_else(|| "Erreur du stockage local (quota dépassé ?)".into())
+}
+
+#[cfg(feature = "offline")]
+impl KeyValueStore for BrowserStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Self::local_storage()?.get_item(key).map_err(js_error)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        Self::local_storage()?
+            .set_item(key, value)
+            .map_err(js_error)
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        Self::local_storage()?.remove_item(key).map_err(js_error)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, String> {
+        let storage = Self::local_storage()?;
+        let length = storage.length().map_err(js_error)?;
+        let mut keys = Vec::new();
+        for index in 0..length {
+            if let Some(key) = storage.key(index).map_err(js_error)? {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// Base SQLite embarquée : une ligne par QR code, le document complet étant
+/// conservé en JSON pour suivre l'évolution du modèle sans migration de schéma.
+/// Les écritures passent par une connexion unique et des transactions.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStorage {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+    rewrite: bool,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    const SCHEMA: &'static str = "CREATE TABLE IF NOT EXISTS saved_qr (
+            id TEXT PRIMARY KEY NOT NULL,
+            created_at TEXT NOT NULL,
+            data TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS saved_qr_created_at ON saved_qr (created_at);
+        CREATE UNIQUE INDEX IF NOT EXISTS saved_qr_slug_unique
+            ON saved_qr (json_extract(data, '$.dynamic.slug'));
+        CREATE TABLE IF NOT EXISTS scan_event (
+            qr_id TEXT NOT NULL,
+            at INTEGER NOT NULL,
+            agent TEXT NOT NULL,
+            referrer TEXT,
+            country TEXT
+        );
+        CREATE INDEX IF NOT EXISTS scan_event_qr_id ON scan_event (qr_id, at);";
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let conn = rusqlite::Connection::open(path).map_err(|e| e.to_string())?;
+        Self::init(conn)
+    }
+
+    /// Ouvre une base existante en lecture seule, sans créer le schéma.
+    pub fn open_read_only(path: &Path) -> Result<Self, String> {
+        if !path.is_file() {
+            return Err(format!("La base {:?} n'existe pas", path));
+        }
+        let conn =
+            rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| e.to_string())?;
+        Ok(Self {
+            conn: std::sync::Mutex::new(conn),
+            rewrite: false,
+        })
+    }
+
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = rusqlite::Connection::open_in_memory().map_err(|e| e.to_string())?;
+        Self::init(conn)
+    }
+
+    fn init(conn: rusqlite::Connection) -> Result<Self, String> {
+        conn.execute_batch(Self::SCHEMA)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            conn: std::sync::Mutex::new(conn),
+            rewrite: true,
+        })
+    }
+
+    /// Réécrit (ou non) au format courant les lignes migrées à la lecture.
+    pub fn rewrite_migrated(mut self, rewrite: bool) -> Self {
+        self.rewrite = rewrite;
+        self
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Révision stockée (0 si la ligne n'existe pas).
+    fn stored_revision(conn: &rusqlite::Connection, id: &QrId) -> Result<u64, String> {
+        use rusqlite::OptionalExtension;
+        let data: Option<String> = conn
+            .query_row(
+                "SELECT data FROM saved_qr WHERE id = ?1",
+                [id.as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match data {
+            Some(data) => Ok(parse_record(&data)?.0.revision),
+            None => Ok(0),
+        }
+    }
+
+    /// Écrit la ligne d'un QR code ; l'index unique sur le slug refuse un
+    /// slug déjà utilisé par une autre ligne.
+    fn write(conn: &rusqlite::Connection, qr: &SavedQr) -> Result<(), SaveError> {
+        let json = serde_json::to_string(qr).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO saved_qr (id, created_at, data) VALUES (?1, ?2, ?3)
+             ON CONFLICT (id) DO UPDATE SET created_at = excluded.created_at, data = excluded.data",
+            rusqlite::params![qr.id.as_str(), qr.created_at, json],
+        )
+        .map_err(|e| match (e.sqlite_error_code(), &qr.dynamic) {
+            (Some(rusqlite::ErrorCode::ConstraintViolation), Some(link)) => {
+                SaveError::Conflict(slug_in_use(link))
+            }
+            _ => SaveError::Other(e.to_string()),
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl QrStorage for SqliteStorage {
+    fn save(&self, qr: &SavedQr) -> Result<u64, SaveError> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let stored = Self::stored_revision(&tx, &qr.id)?;
+        check_revision(&qr.id, stored, qr.revision)?;
+        let next = SavedQr {
+            revision: stored + 1,
+            ..qr.clone()
+        };
+        Self::write(&tx, &next)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(next.revision)
+    }
+
+    fn replace(&self, qr: &SavedQr) -> Result<bool, String> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        if Self::stored_revision(&tx, &qr.id)? != qr.revision {
+            return Ok(false);
+        }
+        Self::write(&tx, qr).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<SavedList, String> {
+        let rows: Vec<(String, String)> = {
+            let conn = self.conn();
+            let mut stmt = conn
+                .prepare("SELECT id, data FROM saved_qr ORDER BY created_at")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        let mut res = SavedList::default();
+        for (id, data) in rows {
+            match parse_record(&data) {
+                Ok((qr, migrated)) => {
+                    if migrated && self.rewrite {
+                        rewrite_migrated(self, &qr);
+                    }
+                    res.codes.push(qr);
+                }
+                Err(error) => {
+                    // Signaler la ligne mais continuer avec les autres
+                    eprintln!("Erreur de migration du QR code {}: {}", id, error);
+                    res.unreadable.push(UnreadableRecord { source: id, error });
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn find(&self, id: &QrId) -> Result<Option<SavedQr>, String> {
+        use rusqlite::OptionalExtension;
+        let data: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT data FROM saved_qr WHERE id = ?1",
+                [id.as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(data) = data else {
+            return Ok(None);
+        };
+        let (qr, migrated) = parse_record(&data)?;
+        if migrated && self.rewrite {
+            rewrite_migrated(self, &qr);
+        }
+        Ok(Some(qr))
+    }
+
+    fn delete(&self, id: &QrId) -> Result<(), String> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let deleted = tx
+            .execute("DELETE FROM saved_qr WHERE id = ?1", [id.as_str()])
+            .map_err(|e| e.to_string())?;
+        if deleted == 0 {
+            return Err(format!("Le QR code {} n'existe pas", id));
+        }
+        tx.execute("DELETE FROM scan_event WHERE qr_id = ?1", [id.as_str()])
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    fn record_scan(&self, scan: &ScanEvent) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "INSERT INTO scan_event (qr_id, at, agent, referrer, country)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    scan.qr_id.as_str(),
+                    scan.at as i64,
+                    scan.agent.code(),
+                    scan.referrer,
+                    scan.country
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn scans(&self, id: &QrId) -> Result<Vec<ScanEvent>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT at, agent, referrer, country FROM scan_event
+                 WHERE qr_id = ?1 ORDER BY at, rowid",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([id.as_str()], |row| {
+                use crate::models::analytics::AgentClass;
+                Ok(ScanEvent {
+                    qr_id: id.clone(),
+                    at: row.get::<_, i64>(0)? as u64,
+                    agent: AgentClass::from_code(&row.get::<_, String>(1)?).unwrap_or_default(),
+                    referrer: row.get(2)?,
+                    country: row.get(3)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    fn find_by_slug(&self, slug: &str) -> Result<Option<SavedQr>, String> {
+        use rusqlite::OptionalExtension;
+        let data: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT data FROM saved_qr WHERE json_extract(data, '$.dynamic.slug') = ?1",
+                [slug],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match data {
+            Some(data) => Ok(Some(parse_record(&data)?.0)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::analytics::AgentClass;
+    use crate::models::qr_code::DynamicLink;
+
+    fn sample(id: &str, created_at: &str) -> SavedQr {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": format!("texte {}", id),
+            "size": 256,
+            "transparent": false,
+            "margin": true,
+            "created_at": created_at,
+        }))
+        .unwrap()
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("qr-craft-test-{}", fastrand::u64(..)))
+    }
+
+    /// Scénario commun à tous les backends.
+    fn exercise(storage: &dyn QrStorage) {
+        assert_eq!(storage.list().unwrap(), SavedList::default());
+
+        let first = sample("qr-1", "100");
+        let second = sample("qr-2", "200");
+        assert_eq!(storage.save(&first).unwrap(), 1);
+        assert_eq!(storage.save(&second).unwrap(), 1);
+
+        let loaded = storage.load(&first.id).unwrap();
+        assert_eq!(loaded.text, "texte qr-1");
+        assert_eq!(loaded.revision, 1);
+
+        let mut updated = loaded.clone();
+        updated.text = "modifié".into();
+        assert_eq!(storage.save(&updated).unwrap(), 2);
+        assert_eq!(storage.load(&first.id).unwrap().text, "modifié");
+
+        // Une édition partie d'une révision périmée est refusée
+        let error = storage.save(&first).unwrap_err();
+        assert!(matches!(error, SaveError::Conflict(_)), "{}", error);
+        assert!(storage.save(&updated).is_err());
+        assert_eq!(storage.load(&first.id).unwrap().text, "modifié");
+
+        // La réécriture conserve la révision et ignore les copies périmées
+        assert!(!storage.replace(&loaded).unwrap());
+        let current = storage.load(&first.id).unwrap();
+        assert!(storage.replace(&current).unwrap());
+        assert_eq!(storage.load(&first.id).unwrap().revision, 2);
+
+        let mut ids: Vec<String> = storage
+            .list()
+            .unwrap()
+            .codes
+            .into_iter()
+            .map(|qr| qr.id.to_string())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["qr-1", "qr-2"]);
+
+        // Les scans sont rattachés à leur QR code et supprimés avec lui
+        assert!(storage.scans(&first.id).unwrap().is_empty());
+        for (at, agent) in [(10, AgentClass::Mobile), (20, AgentClass::Bot)] {
+            storage
+                .record_scan(&ScanEvent {
+                    qr_id: first.id.clone(),
+                    at,
+                    agent,
+                    referrer: Some("example.com".into()),
+                    country: None,
+                })
+                .unwrap();
+        }
+        let scans = storage.scans(&first.id).unwrap();
+        assert_eq!(scans.len(), 2);
+        assert_eq!((scans[0].at, scans[0].agent), (10, AgentClass::Mobile));
+        assert_eq!(scans[1].referrer.as_deref(), Some("example.com"));
+        assert!(storage.scans(&second.id).unwrap().is_empty());
+        assert_eq!(storage.list().unwrap().codes.len(), 2);
+
+        storage.delete(&first.id).unwrap();
+        assert!(storage.scans(&first.id).unwrap().is_empty());
+        assert_eq!(storage.find(&first.id).unwrap(), None);
+        assert!(storage.load(&first.id).is_err());
+        assert!(storage.delete(&first.id).is_err());
+        assert_eq!(storage.list().unwrap().codes.len(), 1);
+    }
+
+    /// Sauvegardes simultanées d'un même QR code depuis la même révision :
+    /// une seule doit aboutir.
+    fn race(storage: &dyn QrStorage) {
+        let qr = sample("qr-5", "500");
+        let successes = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| storage.save(&qr).is_ok()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(|saved| *saved)
+                .count()
+        });
+        assert_eq!(successes, 1);
+        assert_eq!(storage.load(&qr.id).unwrap().revision, 1);
+    }
+
+    fn dynamic(id: &str, slug: &str) -> SavedQr {
+        SavedQr {
+            dynamic: Some(DynamicLink {
+                slug: slug.into(),
+                target: "https://example.com".into(),
+            }),
+            ..sample(id, "0")
+        }
+    }
+
+    /// Unicité des slugs, commune à tous les backends.
+    fn slugs(storage: &dyn QrStorage) {
+        let first = dynamic("qr-1", "promo");
+        storage.save(&first).unwrap();
+        storage.save(&sample("qr-2", "200")).unwrap();
+        assert_eq!(storage.find_by_slug("promo").unwrap().unwrap().id, first.id);
+        assert!(storage.find_by_slug("autre").unwrap().is_none());
+
+        // Un autre QR code ne peut pas prendre le slug, son propriétaire le garde
+        let error = storage.save(&dynamic("qr-2", "promo")).unwrap_err();
+        assert!(matches!(error, SaveError::Conflict(_)), "{}", error);
+        let mut edited = storage.load(&first.id).unwrap();
+        edited.dynamic.as_mut().unwrap().target = "https://example.com/hiver".into();
+        storage.save(&edited).unwrap();
+
+        // Le slug est libéré quand son QR code change de slug ou est supprimé
+        let mut renamed = storage.load(&first.id).unwrap();
+        renamed.dynamic.as_mut().unwrap().slug = "ete".into();
+        storage.save(&renamed).unwrap();
+        assert!(storage.find_by_slug("promo").unwrap().is_none());
+        let claimed = SavedQr {
+            revision: 1,
+            ..dynamic("qr-2", "promo")
+        };
+        storage.save(&claimed).unwrap();
+        assert_eq!(
+            storage.find_by_slug("promo").unwrap().unwrap().id,
+            claimed.id
+        );
+        storage.delete(&first.id).unwrap();
+        assert!(storage.find_by_slug("ete").unwrap().is_none());
+        storage.save(&dynamic("qr-3", "ete")).unwrap();
+    }
+
+    /// Sauvegardes simultanées de QR codes différents avec le même slug :
+    /// une seule doit aboutir.
+    fn slug_race(storage: &dyn QrStorage) {
+        let successes = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let qr = dynamic(&format!("qr-1{}", i), "course");
+                    scope.spawn(move || storage.save(&qr).is_ok())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(|saved| *saved)
+                .count()
+        });
+        assert_eq!(successes, 1);
+        assert!(storage.find_by_slug("course").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_json_dir_storage() {
+        let dir = temp_dir();
+        exercise(&JsonDirStorage::new(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_json_dir_slugs() {
+        let dir = temp_dir();
+        slugs(&JsonDirStorage::new(&dir));
+        slug_race(&JsonDirStorage::new(&dir));
+        // L'index est reconstruit à partir des fichiers par une nouvelle instance
+        let reopened = JsonDirStorage::new(&dir);
+        assert_eq!(
+            reopened.find_by_slug("promo").unwrap().unwrap().id.as_str(),
+            "qr-2"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_json_dir_concurrent_saves() {
+        let dir = temp_dir();
+        let storage = JsonDirStorage::new(&dir);
+        race(&storage);
+        assert!(storage.locks.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_leaves_no_temporary_file() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("qr-1.json");
+        write_atomic(&path, b"premier").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // Écriture impossible : le fichier existant reste intact
+        assert!(write_atomic(&dir.join("absent").join("qr-1.json"), b"x").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_json_dir_reports_invalid_files() {
+        let dir = temp_dir();
+        let storage = JsonDirStorage::new(&dir);
+        storage.save(&sample("qr-1", "100")).unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+        fs::write(
+            dir.join("qr-2.json"),
+            r#"{"id": "qr-2", "schema_version": 99}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "ignoré").unwrap();
+
+        let list = storage.list().unwrap();
+        assert_eq!(list.codes.len(), 1);
+        let mut sources: Vec<&str> = list.unreadable.iter().map(|r| r.source.as_str()).collect();
+        sources.sort();
+        assert_eq!(sources, vec!["broken.json", "qr-2.json"]);
+        // Les fichiers illisibles restent en place
+        assert!(dir.join("broken.json").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    const LEGACY_RECORD: &str = r#"{
+        "id": "qr-9",
+        "text": "ancien",
+        "size": 256,
+        "transparent": false,
+        "margin": true,
+        "created_at": "0",
+        "image_data": "iVBORw0KGgoAAAANSUhEUgAA"
+    }"#;
+
+    #[test]
+    fn test_json_dir_migrates_legacy_files() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("qr-9.json"), LEGACY_RECORD).unwrap();
+        let storage = JsonDirStorage::new(&dir);
+
+        let list = storage.list().unwrap();
+        assert_eq!(list.codes.len(), 1);
+        assert_eq!(list.codes[0].text, "ancien");
+        let rewritten = fs::read_to_string(dir.join("qr-9.json")).unwrap();
+        assert!(!rewritten.contains("image_data"));
+        assert!(rewritten.contains("\"schema_version\": 1"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_json_dir_without_rewrite_keeps_files() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("qr-9.json"), LEGACY_RECORD).unwrap();
+        let storage = JsonDirStorage::new(&dir).rewrite_migrated(false);
+
+        let id = QrId::parse("qr-9").unwrap();
+        assert_eq!(storage.load(&id).unwrap().text, "ancien");
+        assert_eq!(
+            fs::read_to_string(dir.join("qr-9.json")).unwrap(),
+            LEGACY_RECORD
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Magasin en mémoire, à la place du `localStorage` du navigateur.
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, String>>);
+
+    impl MemoryStore {
+        fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+            self.0.lock().unwrap()
+        }
+    }
+
+    impl KeyValueStore for MemoryStore {
+        fn get(&self, key: &str) -> Result<Option<String>, String> {
+            Ok(self.entries().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<(), String> {
+            self.entries().insert(key.into(), value.into());
+            Ok(())
+        }
+
+        fn remove(&self, key: &str) -> Result<(), String> {
+            self.entries().remove(key);
+            Ok(())
+        }
+
+        fn keys(&self) -> Result<Vec<String>, String> {
+            Ok(self.entries().keys().cloned().collect())
+        }
+    }
+
+    #[test]
+    fn test_key_value_storage() {
+        let storage = KeyValueStorage::new(MemoryStore::default());
+        exercise(&storage);
+        race(&storage);
+        assert!(storage.locks.is_empty());
+    }
+
+    #[test]
+    fn test_key_value_storage_slugs() {
+        slugs(&KeyValueStorage::new(MemoryStore::default()));
+        slug_race(&KeyValueStorage::new(MemoryStore::default()));
+    }
+
+    #[test]
+    fn test_key_value_storage_migrates_and_reports() {
+        let store = MemoryStore::default();
+        store.set("qr-craft/qr/qr-9", LEGACY_RECORD).unwrap();
+        store.set("qr-craft/qr/qr-3", "{").unwrap();
+        store.set("theme", "dark").unwrap();
+        let storage = KeyValueStorage::new(store);
+
+        let list = storage.list().unwrap();
+        assert_eq!(list.codes.len(), 1);
+        assert_eq!(list.codes[0].text, "ancien");
+        assert_eq!(list.unreadable.len(), 1);
+        assert_eq!(list.unreadable[0].source, "qr-3");
+        let rewritten = storage.store.get("qr-craft/qr/qr-9").unwrap().unwrap();
+        assert!(!rewritten.contains("image_data"));
+        // Les entrées étrangères à QR Craft sont ignorées et conservées
+        assert_eq!(storage.store.get("theme").unwrap(), Some("dark".into()));
+    }
+
+    #[test]
+    fn test_storage_kind_from_code() {
+        assert_eq!(StorageKind::from_code("json"), Some(StorageKind::Json));
+        assert_eq!(
+            StorageKind::from_code(" SQLite "),
+            Some(StorageKind::Sqlite)
+        );
+        assert_eq!(
+            StorageKind::from_code("browser"),
+            Some(StorageKind::Browser)
+        );
+        assert_eq!(StorageKind::from_code("postgres"), None);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_storage() {
+        exercise(&SqliteStorage::open_in_memory().unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_slugs() {
+        slugs(&SqliteStorage::open_in_memory().unwrap());
+        slug_race(&SqliteStorage::open_in_memory().unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_concurrent_saves() {
+        race(&SqliteStorage::open_in_memory().unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_migrates_legacy_rows() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        storage
+            .conn()
+            .execute(
+                "INSERT INTO saved_qr (id, created_at, data) VALUES ('qr-9', '0', ?1)",
+                [LEGACY_RECORD],
+            )
+            .unwrap();
+        let id = QrId::parse("qr-9").unwrap();
+        assert_eq!(storage.load(&id).unwrap().text, "ancien");
+        let data: String = storage
+            .conn()
+            .query_row("SELECT data FROM saved_qr WHERE id = 'qr-9'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(!data.contains("image_data"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_storage_persists_on_disk() {
+        let dir = temp_dir();
+        let path = dir.join("qr-craft.db");
+        SqliteStorage::open(&path)
+            .unwrap()
+            .save(&sample("qr-7", "700"))
+            .unwrap();
+        let reopened = SqliteStorage::open(&path).unwrap();
+        assert_eq!(reopened.list().unwrap().codes.len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_open_existing_storage_creates_nothing() {
+        let dir = temp_dir();
+        for storage in [StorageKind::Json, StorageKind::Sqlite] {
+            let settings = Settings {
+                data_dir: dir.clone(),
+                storage,
+                ..Settings::default()
+            };
+            assert!(open_existing_storage(&settings).is_err());
+            assert!(!dir.exists());
+        }
+
+        fs::create_dir_all(&dir).unwrap();
+        let settings = Settings {
+            data_dir: dir.clone(),
+            storage: StorageKind::Sqlite,
+            ..Settings::default()
+        };
+        assert!(open_existing_storage(&settings).is_err());
+        assert!(!dir.join("qr-craft.db").exists());
+
+        SqliteStorage::open(&dir.join("qr-craft.db"))
+            .unwrap()
+            .save(&sample("qr-7", "700"))
+            .unwrap();
+        let storage = open_existing_storage(&settings).unwrap();
+        assert_eq!(storage.list().unwrap().codes.len(), 1);
+        assert!(storage.save(&sample("qr-8", "800")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_saved_path_stays_in_data_dir() {
+        let storage = JsonDirStorage::new("data");
+        let id = QrId::parse("qr-42").unwrap();
+        assert_eq!(storage.path(&id), Path::new("data").join("qr-42.json"));
+    }
+}
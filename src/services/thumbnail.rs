@@ -0,0 +1,159 @@
+//! Miniatures des QR codes sauvegardés
+//!
+//! Les enregistrements ne contiennent que les paramètres de génération : les
+//! miniatures affichées dans la liste sont rendues à la demande et gardées en
+//! cache côté serveur.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::models::qr_code::{QrId, RenderOptions, SavedQr};
+use crate::services::qr_code::{render_fingerprint, render_qr_png_bytes};
+
+/// Taille des miniatures, en pixels
+pub const THUMBNAIL_SIZE: u32 = 128;
+
+/// Nombre maximal de miniatures gardées en mémoire
+pub const CACHE_CAPACITY: usize = 512;
+
+/// Rend la miniature PNG d'un QR code sauvegardé, sous forme de data URL.
+pub fn render_thumbnail(qr: &SavedQr) -> Result<String, String> {
+    let options = RenderOptions {
+        size: THUMBNAIL_SIZE,
+        ..qr.render_options()
+    };
+    let bytes = render_qr_png_bytes(&qr.text, &options)?;
+    Ok(format!("data:image/png;base64,{}", base64::encode(&bytes)))
+}
+
+/// Une modification du QR code invalide sa miniature.
+fn fingerprint(qr: &SavedQr) -> u64 {
+    render_fingerprint(&qr.text, &qr.render_options())
+}
+
+/// Cache borné des miniatures, indexé par identifiant.
+pub struct ThumbnailCache {
+    entries: Mutex<HashMap<QrId, (u64, String)>>,
+    capacity: usize,
+}
+
+impl ThumbnailCache {
+    pub fn new(capacity: usize) -> Self {
+        ThumbnailCache {
+            entries: Mutex::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<QrId, (u64, String)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Renvoie la miniature en cache, ou la rend si le QR code a changé.
+    pub fn get_or_render(&self, qr: &SavedQr) -> Result<String, String> {
+        let key = fingerprint(qr);
+        if let Some((cached_key, data_url)) = self.entries().get(&qr.id) {
+            if *cached_key == key {
+                return Ok(data_url.clone());
+            }
+        }
+
+        // Rendu hors du verrou pour ne pas bloquer les autres requêtes
+        let data_url = render_thumbnail(qr)?;
+        let mut entries = self.entries();
+        if entries.len() >= self.capacity && !entries.contains_key(&qr.id) {
+            if let Some(evicted) = entries.keys().next().cloned() {
+                entries.remove(&evicted);
+            }
+        }
+        entries.insert(qr.id.clone(), (key, data_url.clone()));
+        Ok(data_url)
+    }
+
+    pub fn invalidate(&self, id: &QrId) {
+        self.entries().remove(id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+static THUMBNAILS: OnceLock<ThumbnailCache> = OnceLock::new();
+
+/// Cache partagé par les fonctions serveur.
+pub fn thumbnails() -> &'static ThumbnailCache {
+    THUMBNAILS.get_or_init(|| ThumbnailCache::new(CACHE_CAPACITY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, text: &str) -> SavedQr {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": text,
+            "size": 512,
+            "transparent": false,
+            "margin": true,
+            "created_at": "0",
+        }))
+        .unwrap()
+    }
+
+    fn decode(data_url: &str) -> image::RgbaImage {
+        let b64 = data_url.strip_prefix("data:image/png;base64,").unwrap();
+        image::load_from_memory(&base64::decode(b64).unwrap())
+            .unwrap()
+            .to_rgba8()
+    }
+
+    #[test]
+    fn test_thumbnail_uses_thumbnail_size() {
+        let data_url = render_thumbnail(&sample("qr-1", "https://example.com")).unwrap();
+        let image = decode(&data_url);
+        // La taille est arrondie au module près, bien en deçà des 512px enregistrés
+        assert_eq!(image.width(), image.height());
+        assert!(image.width() >= THUMBNAIL_SIZE && image.width() < THUMBNAIL_SIZE + 32);
+    }
+
+    #[test]
+    fn test_cache_reuses_and_refreshes() {
+        let cache = ThumbnailCache::new(8);
+        let mut qr = sample("qr-1", "premier");
+        let first = cache.get_or_render(&qr).unwrap();
+        assert_eq!(cache.get_or_render(&qr).unwrap(), first);
+
+        // Changer un paramètre de rendu produit une nouvelle miniature
+        qr.text = "second".into();
+        let second = cache.get_or_render(&qr).unwrap();
+        assert_ne!(second, first);
+        assert_eq!(cache.len(), 1);
+
+        cache.invalidate(&qr.id);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let cache = ThumbnailCache::new(2);
+        for i in 1..=5 {
+            cache
+                .get_or_render(&sample(&format!("qr-{}", i), "contenu"))
+                .unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_empty_text_is_an_error() {
+        let cache = ThumbnailCache::new(2);
+        assert!(cache.get_or_render(&sample("qr-1", "")).is_err());
+        assert!(cache.is_empty());
+    }
+}